@@ -0,0 +1,409 @@
+use std::{collections::HashMap, io::Cursor, sync::Arc, time::Duration};
+
+use image_processor::{
+    core::{image::Images, pipeline::parse_pipeline, progress::CancellationToken},
+    utils::{
+        image_io::{decode_image, encode_image, OutputFormat, OutputOptions, ReadOptions},
+        json::json_string,
+        logging::init_stdout_logger,
+        metadata::ImageMetadata,
+        statistics::ImageStatistics,
+    },
+};
+use log::{info, warn};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
+    time::timeout,
+};
+
+/// Address to listen on, unless one is given as the first argument
+const ADDRESS: &str = "127.0.0.1:8080";
+const MAX_CONNECTIONS: usize = 64;
+/// Pipelines running at once on the blocking thread pool, including timed out ones
+/// that have not reached their next operation yet
+const MAX_JOBS: usize = 8;
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+/// Largest decoded image, and largest image any step of a pipeline may produce
+const MAX_PIXELS: u64 = 40_000_000;
+const MAX_DIMENSION: u32 = 16_384;
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+const PROCESS_TIMEOUT: Duration = Duration::from_secs(60);
+
+static JOBS: Semaphore = Semaphore::const_new(MAX_JOBS);
+
+/// Local HTTP service running operation pipelines on posted images.
+///
+/// - `POST /process?pipeline=...&format=png` answers with the processed image,
+///   encoded as `format`, PNG by default
+/// - `POST /stats?pipeline=...` answers with the `ImageStatistics` of the image,
+///   after the optional pipeline, as JSON
+///
+/// Pipelines use the description of `parse_pipeline`, URL encoded. Errors are answered as
+/// JSON `{"error": "..."}` with a 4xx or 5xx status.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    init_stdout_logger()?;
+
+    let address = std::env::args().nth(1).unwrap_or(ADDRESS.to_string());
+    let listener = TcpListener::bind(&address).await?;
+    info!("Listening on {}", listener.local_addr()?);
+
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        let permit = connections.clone().acquire_owned().await?;
+        let (stream, peer) = listener.accept().await?;
+        tokio::spawn(async move {
+            if let Err(error) = handle_connection(stream).await {
+                warn!("Connection with {} failed: {}", peer, error);
+            }
+            drop(permit);
+        });
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    reason: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn new(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status: 200,
+            reason: "OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: u16, reason: &'static str, message: &str) -> Self {
+        Self {
+            status,
+            reason,
+            content_type: "application/json",
+            body: format!("{{\"error\": {}}}\n", json_string(message)).into_bytes(),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.reason,
+            self.content_type,
+            self.body.len()
+        )
+        .into_bytes();
+        bytes.extend_from_slice(&self.body);
+
+        bytes
+    }
+}
+
+/// Answers one request, then closes the connection
+async fn handle_connection(mut stream: TcpStream) -> Result<(), Box<dyn std::error::Error>> {
+    let response = match timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(request)) => {
+            let request_line = format!("{} {}", request.method, request.path);
+            let response = route(request).await;
+            info!(
+                "{} -> {} {}",
+                request_line, response.status, response.reason
+            );
+            response
+        }
+        Ok(Err(response)) => response,
+        Err(_) => Response::error(408, "Request Timeout", "Reading the request timed out"),
+    };
+    stream.write_all(&response.to_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+/// Reads the head and the body of a request, or answers with an error when it is over the limits
+async fn read_request(stream: &mut TcpStream) -> Result<Request, Response> {
+    let read_error = |error: std::io::Error| {
+        Response::error(400, "Bad Request", &format!("Reading failed: {}", error))
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunk = [0_u8; 8192];
+    let head_end = loop {
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position;
+        }
+        if buffer.len() > MAX_HEAD_BYTES {
+            return Err(Response::error(
+                431,
+                "Request Header Fields Too Large",
+                "The request head is too large",
+            ));
+        }
+        let read = stream.read(&mut chunk).await.map_err(read_error)?;
+        if read == 0 {
+            return Err(Response::error(400, "Bad Request", "Incomplete request"));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let request_line: Vec<&str> = lines.next().unwrap_or_default().split(' ').collect();
+    let [method, target, _version] = request_line[..] else {
+        return Err(Response::error(
+            400,
+            "Bad Request",
+            "Malformed request line",
+        ));
+    };
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    if headers.contains_key("transfer-encoding") {
+        return Err(Response::error(
+            411,
+            "Length Required",
+            "Chunked bodies are not supported, send a Content-Length",
+        ));
+    }
+    let content_length: usize = match headers.get("content-length") {
+        Some(length) => length
+            .parse()
+            .map_err(|_| Response::error(400, "Bad Request", "Invalid Content-Length"))?,
+        None => 0,
+    };
+    if content_length > MAX_BODY_BYTES {
+        return Err(Response::error(
+            413,
+            "Payload Too Large",
+            &format!("The body is limited to {} bytes", MAX_BODY_BYTES),
+        ));
+    }
+    if headers
+        .get("expect")
+        .map(|expect| expect.to_ascii_lowercase())
+        == Some("100-continue".to_string())
+    {
+        stream
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+            .await
+            .map_err(read_error)?;
+    }
+
+    let mut body = buffer[head_end + 4..].to_vec();
+    body.truncate(content_length);
+    while body.len() < content_length {
+        let read = stream.read(&mut chunk).await.map_err(read_error)?;
+        if read == 0 {
+            return Err(Response::error(400, "Bad Request", "Incomplete body"));
+        }
+        let missing = content_length - body.len();
+        body.extend_from_slice(&chunk[..read.min(missing)]);
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect();
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query,
+        body,
+    })
+}
+
+/// Decodes `%xx` escapes and `+` for spaces in a query string
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    index += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+async fn route(request: Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/process") => process(request).await,
+        ("POST", "/stats") => stats(request).await,
+        (_, "/process") | (_, "/stats") => {
+            Response::error(405, "Method Not Allowed", "Only POST is supported")
+        }
+        _ => Response::error(404, "Not Found", "Unknown path"),
+    }
+}
+
+/// What went wrong while running a request on the blocking thread pool
+enum JobError {
+    Invalid(String),
+    TooLarge(String),
+    Panicked,
+    Cancelled,
+}
+
+fn check_pixels((width, height): (u32, u32)) -> Result<(), JobError> {
+    if width as u64 * height as u64 > MAX_PIXELS {
+        return Err(JobError::TooLarge(format!(
+            "An image of {}x{} is above the limit of {} pixels",
+            width, height, MAX_PIXELS
+        )));
+    }
+
+    Ok(())
+}
+
+/// Decodes the posted image and runs the pipeline of the query over it, on the blocking
+/// thread pool and within the processing timeout. A timed out job is cancelled, and keeps
+/// its slot of `MAX_JOBS` until it stops before its next operation.
+async fn run_pipeline<F>(request: Request, finish: F) -> Response
+where
+    F: FnOnce(Images<u8>, &ImageMetadata) -> Result<Response, String> + Send + 'static,
+{
+    let description = request.query.get("pipeline").cloned().unwrap_or_default();
+    let operations = match parse_pipeline::<u8>(&description, MAX_DIMENSION) {
+        Ok(operations) => operations,
+        Err(error) => return Response::error(400, "Bad Request", &error.to_string()),
+    };
+
+    let cancellation = CancellationToken::new();
+    let job_cancellation = cancellation.clone();
+    let job = async move {
+        // Held by the blocking job until it ends, even when the request timed out before
+        let permit = JOBS
+            .acquire()
+            .await
+            .map_err(|error| JobError::Invalid(error.to_string()))?;
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            let (width, height) = image::ImageReader::new(Cursor::new(&request.body))
+                .with_guessed_format()
+                .map_err(|error| JobError::Invalid(error.to_string()))?
+                .into_dimensions()
+                .map_err(|error| JobError::Invalid(error.to_string()))?;
+            check_pixels((width, height))?;
+
+            let (mut image, metadata) = decode_image::<u8>(
+                &request.body,
+                None,
+                &ReadOptions::new().with_auto_orient(true),
+            )
+            .map_err(|error| JobError::Invalid(error.to_string()))?;
+            for operation in operations.iter() {
+                if job_cancellation.is_cancelled() {
+                    return Err(JobError::Cancelled);
+                }
+                let input_size = (image.get_width(), image.get_height());
+                operation
+                    .check_input_size(input_size)
+                    .map_err(|error| JobError::Invalid(error.to_string()))?;
+                if let Some(size) = operation.output_size(input_size) {
+                    check_pixels(size)?;
+                }
+                image = operation.apply(&image);
+            }
+
+            finish(image, &metadata).map_err(JobError::Invalid)
+        })
+        .await
+        .map_err(|_| JobError::Panicked)?
+    };
+
+    match timeout(PROCESS_TIMEOUT, job).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(JobError::Invalid(message))) => {
+            Response::error(422, "Unprocessable Content", &message)
+        }
+        Ok(Err(JobError::TooLarge(message))) => Response::error(413, "Payload Too Large", &message),
+        Ok(Err(JobError::Panicked)) => Response::error(
+            500,
+            "Internal Server Error",
+            "The pipeline failed on this image",
+        ),
+        Ok(Err(JobError::Cancelled)) | Err(_) => {
+            // The blocking thread can not be stopped mid operation, it stops before the next one
+            cancellation.cancel();
+            Response::error(503, "Service Unavailable", "Processing timed out")
+        }
+    }
+}
+
+async fn process(request: Request) -> Response {
+    let format_name = request
+        .query
+        .get("format")
+        .cloned()
+        .unwrap_or(String::from("png"));
+    let Some(format) = OutputFormat::from_extension(&format_name) else {
+        return Response::error(
+            400,
+            "Bad Request",
+            &format!("Unknown format {:?}", format_name),
+        );
+    };
+
+    run_pipeline(request, move |image, metadata| {
+        let bytes = encode_image(&image, &OutputOptions::new().with_format(format), metadata)
+            .map_err(|error| error.to_string())?;
+        Ok(Response::new(content_type(format), bytes))
+    })
+    .await
+}
+
+async fn stats(request: Request) -> Response {
+    run_pipeline(request, |image, _| {
+        Ok(Response::new(
+            "application/json",
+            ImageStatistics::new(&image).to_json().into_bytes(),
+        ))
+    })
+    .await
+}
+
+fn content_type(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Png => "image/png",
+        OutputFormat::Jpeg => "image/jpeg",
+        OutputFormat::WebP => "image/webp",
+        OutputFormat::Bmp => "image/bmp",
+        OutputFormat::Tiff => "image/tiff",
+        OutputFormat::Pnm => "image/x-portable-anymap",
+        OutputFormat::Gif => "image/gif",
+    }
+}
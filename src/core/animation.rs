@@ -0,0 +1,107 @@
+use super::{image::Images, operations::Operation, sample::Sample};
+
+/// What happens to the area of a frame once its delay has passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposal {
+    /// The frame stays, and the next frame is drawn over it
+    None,
+    /// The area is cleared to transparent
+    Background,
+    /// The area goes back to what it was before the frame was drawn
+    Previous,
+}
+
+/// One frame of an animation, covering the whole canvas
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationFrame<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    image: Images<T>,
+    delay_ms: u32,
+    disposal: Disposal,
+}
+
+impl<T> AnimationFrame<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    pub fn new(image: Images<T>, delay_ms: u32, disposal: Disposal) -> Self {
+        Self {
+            image,
+            delay_ms,
+            disposal,
+        }
+    }
+
+    pub fn get_image(&self) -> &Images<T> {
+        &self.image
+    }
+
+    pub fn get_delay_ms(&self) -> u32 {
+        self.delay_ms
+    }
+
+    pub fn get_disposal(&self) -> Disposal {
+        self.disposal
+    }
+}
+
+/// Frames of an animated GIF, APNG or WebP image.
+/// Decoders compose every frame onto the full canvas, so read frames use `Disposal::Background`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    frames: Vec<AnimationFrame<T>>,
+    loop_count: u32,
+}
+
+impl<T> Animation<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    /// `loop_count` is the number of times the animation plays, 0 plays it forever
+    pub fn new(frames: Vec<AnimationFrame<T>>, loop_count: u32) -> Self {
+        Self { frames, loop_count }
+    }
+
+    pub fn get_frames(&self) -> &[AnimationFrame<T>] {
+        &self.frames
+    }
+
+    pub fn get_loop_count(&self) -> u32 {
+        self.loop_count
+    }
+
+    /// Width and height of the canvas, taken from the first frame
+    pub fn get_dimensions(&self) -> Option<(u32, u32)> {
+        self.frames
+            .first()
+            .map(|frame| (frame.image.get_width(), frame.image.get_height()))
+    }
+}
+
+impl<T> Animation<T>
+where
+    T: Sample,
+{
+    /// Runs the operations over every frame in order, keeping delays and disposal
+    pub fn apply_operations(&self, operations: &[Box<dyn Operation<T>>]) -> Self {
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| {
+                let mut new_image = frame.image.clone();
+                for ops in operations.iter() {
+                    new_image = ops.apply(&new_image);
+                }
+
+                AnimationFrame::new(new_image, frame.delay_ms, frame.disposal)
+            })
+            .collect();
+
+        Self::new(frames, self.loop_count)
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use tokio::fs;
+
+use super::{operations::Operation, sample::Sample};
+
+/// When `process_images` leaves an image alone on a repeated run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IncrementalMode {
+    /// Every image is processed
+    #[default]
+    Off,
+    /// Images whose output was modified after the input are skipped
+    SkipNewerOutputs,
+    /// Images are skipped when neither their bytes nor the pipeline changed since the output
+    /// was written, tracked in a cache file in the output folder
+    ContentHash,
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64 bit FNV-1a hash, stable across runs and platforms
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    fnv1a_continue(FNV_OFFSET_BASIS, bytes)
+}
+
+fn fnv1a_continue(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Hash of the operation fingerprints, the sample type, and `settings`,
+/// the fingerprint of any other settings that change the written files
+pub fn pipeline_fingerprint<T>(operations: &[Box<dyn Operation<T>>], settings: &str) -> u64
+where
+    T: Sample,
+{
+    let mut hash = fnv1a_continue(FNV_OFFSET_BASIS, format!("{:?}", T::FORMAT).as_bytes());
+    for operation in operations.iter() {
+        hash = fnv1a_continue(hash, format!("{};", operation.fingerprint()).as_bytes());
+    }
+
+    fnv1a_continue(hash, settings.as_bytes())
+}
+
+/// Cache key of an input file processed by a pipeline
+pub fn cache_key(input_bytes: &[u8], pipeline_fingerprint: u64) -> u64 {
+    fnv1a_continue(fnv1a(input_bytes), &pipeline_fingerprint.to_le_bytes())
+}
+
+/// Cache keys of written outputs, saved as lines of `key output_path`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessCache {
+    entries: HashMap<String, u64>,
+}
+
+impl ProcessCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the cache file, or an empty cache when it is missing or unreadable
+    pub async fn load(cache_path: &str) -> Self {
+        let Ok(contents) = fs::read_to_string(cache_path).await else {
+            return Self::new();
+        };
+        let entries = contents
+            .lines()
+            .filter_map(|line| {
+                let (key, output) = line.split_once(' ')?;
+                Some((output.to_string(), u64::from_str_radix(key, 16).ok()?))
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    pub async fn save(&self, cache_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut lines: Vec<String> = self
+            .entries
+            .iter()
+            .map(|(output, key)| format!("{:016x} {}", key, output))
+            .collect();
+        lines.sort();
+        fs::write(cache_path, lines.join("\n") + "\n").await?;
+
+        Ok(())
+    }
+
+    /// Whether `output` was written from the same input and pipeline
+    pub fn is_fresh(&self, output: &str, key: u64) -> bool {
+        self.entries.get(output) == Some(&key)
+    }
+
+    pub fn insert(&mut self, output: &str, key: u64) {
+        self.entries.insert(output.to_string(), key);
+    }
+
+    pub fn remove(&mut self, output: &str) {
+        self.entries.remove(output);
+    }
+}
@@ -1,5 +1,18 @@
-use super::pixel::Pixels;
+use image::{DynamicImage, ImageBuffer, Pixel};
 
+use super::{pixel::Pixels, sample::Sample};
+use crate::utils::{
+    image_io::{decode_image, encode_image, OutputFormat, OutputOptions, ReadOptions},
+    metadata::ImageMetadata,
+};
+
+/// Image stored as interleaved samples, `channels` samples per pixel:
+/// - 1: gray
+/// - 2: gray, alpha
+/// - 3: red, green, blue
+/// - 4: red, green, blue, alpha
+///
+/// Pixels handed to a gray image store their red channel as the gray value.
 #[derive(Debug, Clone)]
 pub struct Images<T>
 where
@@ -8,7 +21,38 @@ where
     width: u32,
     height: u32,
     channels: u8,
-    image_data: Vec<Pixels<T>>,
+    image_data: Vec<T>,
+}
+
+/// Appends the samples of `pix` that an image with `channels` channels stores
+fn pack_pixel<T>(samples: &mut Vec<T>, channels: u8, pix: &Pixels<T>)
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    match channels {
+        1 => samples.push(pix.get_red()),
+        2 => samples.extend([pix.get_red(), pix.get_alpha()]),
+        3 => samples.extend([pix.get_red(), pix.get_green(), pix.get_blue()]),
+        _ => samples.extend([
+            pix.get_red(),
+            pix.get_green(),
+            pix.get_blue(),
+            pix.get_alpha(),
+        ]),
+    }
+}
+
+/// Expands the samples of one pixel back to RGBA, with opaque alpha when it is not stored
+fn unpack_pixel<T>(samples: &[T]) -> Pixels<T>
+where
+    T: Sample,
+{
+    match samples.len() {
+        1 => Pixels::new(samples[0], samples[0], samples[0], T::MAX_VALUE),
+        2 => Pixels::new(samples[0], samples[0], samples[0], samples[1]),
+        3 => Pixels::new(samples[0], samples[1], samples[2], T::MAX_VALUE),
+        _ => Pixels::new(samples[0], samples[1], samples[2], samples[3]),
+    }
 }
 
 impl<T> Images<T>
@@ -27,12 +71,28 @@ where
         self.channels
     }
 
-    pub fn get_image(&self) -> Vec<Pixels<T>> {
-        // If i use self instead of &self, we take ownership of self
-        self.image_data.clone()
+    /// Gray + alpha and RGBA images store an alpha channel
+    pub fn has_alpha(&self) -> bool {
+        matches!(self.channels, 2 | 4)
     }
 
+    /// Channel counts outside 1..=4 are stored as RGBA
     pub fn new(width: u32, height: u32, channels: u8, image_data: Vec<Pixels<T>>) -> Self {
+        let channels = if (1..=4).contains(&channels) {
+            channels
+        } else {
+            4
+        };
+        let mut samples = Vec::with_capacity(image_data.len() * channels as usize);
+        for pix in image_data.iter() {
+            pack_pixel(&mut samples, channels, pix);
+        }
+
+        Self::from_samples(width, height, channels, samples)
+    }
+
+    /// Creates an image from interleaved samples, `channels` samples per pixel
+    pub fn from_samples(width: u32, height: u32, channels: u8, image_data: Vec<T>) -> Self {
         Self {
             width,
             height,
@@ -41,16 +101,27 @@ where
         }
     }
 
-    pub fn get_pixel_at(&self, x: u32, y: u32) -> Result<Pixels<T>, Box<dyn std::error::Error>> {
+    pub fn get_samples(&self) -> &[T] {
+        &self.image_data
+    }
+
+    pub fn get_samples_mut(&mut self) -> &mut [T] {
+        &mut self.image_data
+    }
+
+    pub fn into_samples(self) -> Vec<T> {
+        self.image_data
+    }
+
+    /// Samples of the pixel at (x, y), `channels` long
+    pub fn get_pixel_samples(&self, x: u32, y: u32) -> Option<&[T]> {
         if x >= self.width || y >= self.height {
-            return Err(format!("Coordinates out of bounds for x:{:?} and y {:?}", x, y).into());
+            return None;
         }
+        let location = (y as usize * self.width as usize + x as usize) * self.channels as usize;
 
-        let location = y * self.width + x;
-        match self.image_data.get(location as usize) {
-            Some(pixel) => Ok(pixel.clone()),
-            None => Err(("Pixel not found").into()),
-        }
+        self.image_data
+            .get(location..location + self.channels as usize)
     }
 
     pub fn set_pixel_at(
@@ -59,23 +130,95 @@ where
         y: u32,
         pixel: Pixels<T>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let location = x * self.width + y;
-        if self.image_data.len() < (location - 1).try_into().unwrap() {
+        if x >= self.width || y >= self.height {
+            return Err(format!("Coordinates out of bounds for x:{:?} and y {:?}", x, y).into());
+        }
+        let channels = self.channels as usize;
+        let location = (y as usize * self.width as usize + x as usize) * channels;
+        if self.image_data.len() < location + channels {
             return Err("Location more than the image length".into());
         }
-        self.image_data.insert(location as usize, pixel);
+
+        let mut samples = Vec::with_capacity(channels);
+        pack_pixel(&mut samples, self.channels, &pixel);
+        self.image_data[location..location + channels].copy_from_slice(&samples);
 
         Ok(())
     }
 
     pub fn add_pixel(&mut self, pix: Pixels<T>) {
-        self.image_data.push(pix);
+        pack_pixel(&mut self.image_data, self.channels, &pix);
+    }
+
+    /// Samples in planar layout: all samples of the first channel, then the second, and so on
+    pub fn to_planar(&self) -> Vec<T> {
+        let channels = self.channels as usize;
+        (0..channels)
+            .flat_map(|channel| {
+                self.image_data
+                    .iter()
+                    .skip(channel)
+                    .step_by(channels)
+                    .copied()
+            })
+            .collect()
+    }
+
+    /// Creates an image from samples in planar layout, see `to_planar`
+    pub fn from_planar(width: u32, height: u32, channels: u8, planes: &[T]) -> Self {
+        let plane_len = width as usize * height as usize;
+        let image_data = (0..plane_len)
+            .flat_map(|index| {
+                (0..channels as usize).map(move |channel| planes[channel * plane_len + index])
+            })
+            .collect();
+
+        Self::from_samples(width, height, channels, image_data)
+    }
+}
+
+impl<T> Images<T>
+where
+    T: Sample,
+{
+    pub fn get_image(&self) -> Vec<Pixels<T>> {
+        self.image_data
+            .chunks_exact(self.channels as usize)
+            .map(unpack_pixel)
+            .collect()
+    }
+
+    pub fn get_pixel_at(&self, x: u32, y: u32) -> Result<Pixels<T>, Box<dyn std::error::Error>> {
+        if x >= self.width || y >= self.height {
+            return Err(format!("Coordinates out of bounds for x:{:?} and y {:?}", x, y).into());
+        }
+
+        match self.get_pixel_samples(x, y) {
+            Some(samples) => Ok(unpack_pixel(samples)),
+            None => Err(("Pixel not found").into()),
+        }
+    }
+
+    /// Decodes an encoded image held in memory, with the format sniffed from the magic bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let (image, _) = decode_image(bytes, None, &ReadOptions::default())?;
+
+        Ok(image)
+    }
+
+    /// Encodes the image in memory with default encoder settings
+    pub fn to_bytes(&self, format: OutputFormat) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        encode_image(
+            self,
+            &OutputOptions::new().with_format(format),
+            &ImageMetadata::default(),
+        )
     }
 }
 
 impl<T> PartialEq for Images<T>
 where
-    T: Copy + Clone + From<u8> + Into<f64> + std::cmp::PartialEq,
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
         self.width == other.width
@@ -84,3 +227,62 @@ where
             && self.image_data == other.image_data
     }
 }
+
+/// Takes over the buffer without copying
+impl<P> From<ImageBuffer<P, Vec<P::Subpixel>>> for Images<P::Subpixel>
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    fn from(buffer: ImageBuffer<P, Vec<P::Subpixel>>) -> Self {
+        let (width, height) = buffer.dimensions();
+        Self::from_samples(width, height, P::CHANNEL_COUNT, buffer.into_raw())
+    }
+}
+
+/// Takes over the sample buffer without copying, the channel count must match `P`
+impl<P, T> TryFrom<Images<T>> for ImageBuffer<P, Vec<T>>
+where
+    P: Pixel<Subpixel = T>,
+    T: Sample,
+{
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(image: Images<T>) -> Result<Self, Self::Error> {
+        if image.get_channels() != P::CHANNEL_COUNT {
+            return Err(format!(
+                "Image has {:?} channels, the buffer expects {:?}",
+                image.get_channels(),
+                P::CHANNEL_COUNT
+            )
+            .into());
+        }
+        let (width, height) = (image.get_width(), image.get_height());
+
+        ImageBuffer::from_raw(width, height, image.into_samples())
+            .ok_or_else(|| "Image data does not match its width, height and channels".into())
+    }
+}
+
+/// Takes over the buffer when it already has the bit depth of `T`, converts it otherwise
+impl<T> From<DynamicImage> for Images<T>
+where
+    T: Sample,
+{
+    fn from(image: DynamicImage) -> Self {
+        T::from_dynamic_image(image)
+    }
+}
+
+/// Takes over the sample buffer, except for float gray images which are expanded to RGB
+impl<T> TryFrom<Images<T>> for DynamicImage
+where
+    T: Sample,
+{
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(image: Images<T>) -> Result<Self, Self::Error> {
+        T::into_dynamic_image(image)
+            .ok_or_else(|| "Image data does not match its width, height and channels".into())
+    }
+}
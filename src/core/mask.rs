@@ -0,0 +1,235 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    transformations::resize::ResizeBilinearInterpolation,
+    utils::color_space_converter::{linear_to_sample, sample_to_linear},
+};
+
+use super::{cache::fnv1a, image::Images, operations::Operation, pixel::Pixels, sample::Sample};
+
+/// Region of the image in which a masked operation takes effect.
+/// - Image: grayscale mask, black keeps the original pixel and white takes the operation result.
+///   Masks of a different size than the image are sampled with nearest neighbour.
+/// - Rectangle and Ellipse: hard-edged regions, fully applied inside and untouched outside.
+#[derive(Debug)]
+pub enum MaskRegion<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    Image(Images<T>),
+    Rectangle {
+        top_left: (u32, u32),
+        width: u32,
+        height: u32,
+    },
+    Ellipse {
+        center: (u32, u32),
+        radius_x: u32,
+        radius_y: u32,
+    },
+}
+
+impl<T> MaskRegion<T>
+where
+    T: Sample,
+{
+    /// Blending weight in 0.0..=1.0 for the pixel at (x, y) of an image of the given size
+    fn weight_at(&self, x: u32, y: u32, width: u32, height: u32) -> f64 {
+        match self {
+            MaskRegion::Image(mask) => {
+                let mask_x = (x as u64 * mask.get_width() as u64 / width as u64) as u32;
+                let mask_y = (y as u64 * mask.get_height() as u64 / height as u64) as u32;
+                match mask.get_pixel_at(mask_x, mask_y) {
+                    Ok(pix) => {
+                        (pix.get_red().to_unit()
+                            + pix.get_green().to_unit()
+                            + pix.get_blue().to_unit())
+                            / 3.0
+                    }
+                    Err(_) => 0.0,
+                }
+            }
+            MaskRegion::Rectangle {
+                top_left,
+                width: rect_width,
+                height: rect_height,
+            } => {
+                let inside_x = x >= top_left.0 && x - top_left.0 < *rect_width;
+                let inside_y = y >= top_left.1 && y - top_left.1 < *rect_height;
+                if inside_x && inside_y {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            MaskRegion::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+            } => {
+                if *radius_x == 0 || *radius_y == 0 {
+                    return 0.0;
+                }
+                let dx = (x as f64 - center.0 as f64) / *radius_x as f64;
+                let dy = (y as f64 - center.1 as f64) / *radius_y as f64;
+                if dx * dx + dy * dy <= 1.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Applies any operation only within a mask, blending its result with the original image.
+/// The output always keeps the size of the input image. Results that are a region of the input
+/// are placed at their `Operation::output_offset` (the 3x3 kernel filters drop a 1 pixel border
+/// and are centred, a crop stays at its corner), and the uncovered pixels keep the original.
+/// Results without an offset, such as resized images, are resampled back to the input size
+/// with bilinear interpolation before blending.
+pub struct Masked<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    operation: Box<dyn Operation<T>>,
+    mask: MaskRegion<T>,
+    linear_light: bool,
+}
+
+impl<T> Masked<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    pub fn new(operation: Box<dyn Operation<T>>, mask: MaskRegion<T>) -> Self {
+        Self {
+            operation,
+            mask,
+            linear_light: false,
+        }
+    }
+
+    /// Blends the color channels in linear light, avoiding dark fringes
+    /// along soft mask edges. The resampling of resized results follows the same setting.
+    pub fn with_linear_light(mut self, linear_light: bool) -> Self {
+        self.linear_light = linear_light;
+        self
+    }
+}
+
+impl<T> Operation<T> for Masked<T>
+where
+    T: Sample,
+{
+    fn apply(&self, old_image: &Images<T>) -> Images<T> {
+        let width = old_image.get_width();
+        let height = old_image.get_height();
+
+        let mut operated_image = self.operation.apply(old_image);
+        let offset = self.operation.output_offset(
+            (width, height),
+            (operated_image.get_width(), operated_image.get_height()),
+        );
+        let (offset_x, offset_y) = match offset {
+            Some(offset) => offset,
+            None => {
+                operated_image = ResizeBilinearInterpolation::new(width, height)
+                    .with_linear_light(self.linear_light)
+                    .apply(&operated_image);
+                (0, 0)
+            }
+        };
+        let mask = &self.mask;
+        let linear_light = self.linear_light;
+
+        let new_pixel = (0..height)
+            .into_par_iter()
+            .flat_map(|y_index| {
+                (0..width)
+                    .into_par_iter()
+                    .map(|x_index| {
+                        let original = old_image.get_pixel_at(x_index, y_index).unwrap();
+                        let weight = mask.weight_at(x_index, y_index, width, height);
+                        if weight <= 0.0 || x_index < offset_x || y_index < offset_y {
+                            return original;
+                        }
+                        let operated = match operated_image
+                            .get_pixel_at(x_index - offset_x, y_index - offset_y)
+                        {
+                            Ok(pix) => pix,
+                            Err(_) => return original,
+                        };
+
+                        let blend = |orig: T, op: T, linearize: bool| -> T {
+                            let weight = weight.min(1.0);
+                            if linearize {
+                                let orig = sample_to_linear(orig);
+                                linear_to_sample(orig + (sample_to_linear(op) - orig) * weight)
+                            } else {
+                                let orig = orig.to_unit();
+                                T::from_unit(orig + (op.to_unit() - orig) * weight)
+                            }
+                        };
+                        Pixels::new(
+                            blend(original.get_red(), operated.get_red(), linear_light),
+                            blend(original.get_green(), operated.get_green(), linear_light),
+                            blend(original.get_blue(), operated.get_blue(), linear_light),
+                            blend(original.get_alpha(), operated.get_alpha(), false),
+                        )
+                    })
+                    .collect::<Vec<Pixels<T>>>()
+            })
+            .collect::<Vec<Pixels<T>>>();
+
+        Images::new(width, height, old_image.get_channels(), new_pixel)
+    }
+
+    fn fingerprint(&self) -> String {
+        let mask = match &self.mask {
+            MaskRegion::Image(mask) => {
+                let samples: Vec<u8> = mask
+                    .get_samples()
+                    .iter()
+                    .flat_map(|sample| sample.to_f64().to_le_bytes())
+                    .collect();
+                format!(
+                    "image:{}x{}:{:016x}",
+                    mask.get_width(),
+                    mask.get_height(),
+                    fnv1a(&samples)
+                )
+            }
+            MaskRegion::Rectangle {
+                top_left,
+                width,
+                height,
+            } => format!(
+                "rectangle:{},{},{},{}",
+                top_left.0, top_left.1, width, height
+            ),
+            MaskRegion::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+            } => format!(
+                "ellipse:{},{},{},{}",
+                center.0, center.1, radius_x, radius_y
+            ),
+        };
+
+        format!(
+            "masked({}):{},{}",
+            self.operation.fingerprint(),
+            mask,
+            self.linear_light
+        )
+    }
+
+    fn check_input_size(&self, input_size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
+        self.operation.check_input_size(input_size)
+    }
+
+    fn output_size(&self, input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some(input_size)
+    }
+}
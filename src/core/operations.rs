@@ -1,142 +1,608 @@
-#![allow(clippy::ptr_arg)]
-
 use std::{
-    fmt::Debug,
-    hash::Hash,
     path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
-use image::{GenericImageView, ImageReader, Pixel};
-use tokio::fs;
+use log::{debug, info, warn};
+use tokio::{
+    fs,
+    sync::Semaphore,
+    task::{JoinError, JoinSet},
+};
 
 use crate::utils::{
-    image_io::{image_reader, image_writer},
-    statistics::{compute_histogram, compute_mean, compute_variance, print_histogram},
+    directory::{find_images, DirectoryOptions},
+    image_io::{
+        decode_image, image_reader_with_options, image_writer_with_options, OutputOptions,
+        ReadOptions,
+    },
+    metadata::ImageMetadata,
+    statistics::{ImageStatistics, StatisticsFormat},
 };
 
-use super::image::Images;
+use super::{
+    cache::{cache_key, pipeline_fingerprint, IncrementalMode, ProcessCache},
+    image::Images,
+    progress::{CancellationToken, ProgressEvent, ProgressHandler},
+    report::{BatchReport, FailedFile, ProcessedFile, SkippedFile},
+    sample::Sample,
+};
 
-pub trait Operation<T>
+pub trait Operation<T>: Send + Sync
 where
     T: Copy + Clone + From<u8> + std::cmp::PartialEq,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T>;
+
+    /// Border in pixels the operation drops on each side of the image, and reads around
+    /// each output pixel, which lets `core::tiling` run it tile by tile.
+    /// `None` for operations whose output does not follow from a local neighborhood,
+    /// such as geometric transformations.
+    fn tile_halo(&self) -> Option<u32> {
+        None
+    }
+
+    /// Checks that the operation can run on an input of `input_size`, so callers can report
+    /// an error instead of the operation panicking. By default inputs need one pixel more than
+    /// the `tile_halo` border on both sides.
+    fn check_input_size(&self, input_size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
+        match self.tile_halo() {
+            Some(halo) if halo > 0 && (input_size.0 <= 2 * halo || input_size.1 <= 2 * halo) => {
+                Err(format!(
+                    "An image of {}x{} is too small for a border of {} pixel(s)",
+                    input_size.0, input_size.1, halo
+                )
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Size of the output for an input of `input_size`, which lets a pipeline be checked
+    /// before it allocates anything. By default it follows from `tile_halo`.
+    /// `None` when the size is only known once the operation ran.
+    fn output_size(&self, input_size: (u32, u32)) -> Option<(u32, u32)> {
+        self.tile_halo().map(|halo| {
+            (
+                input_size.0.saturating_sub(2 * halo),
+                input_size.1.saturating_sub(2 * halo),
+            )
+        })
+    }
+
+    /// Settings of the operation that change its output, which `core::cache` hashes to tell
+    /// pipelines apart. The default is the type name only, so operations with settings
+    /// should override it.
+    fn fingerprint(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+
+    /// Position of the top left corner of an output of `output_size` within an input of
+    /// `input_size`, when the output is a region of the input left in place, which lets
+    /// `core::mask::Masked` blend it at the right pixels. By default smaller outputs are centred,
+    /// as the neighborhood filters drop an equal border on every side.
+    /// `None` for outputs not aligned with the input, such as resized images.
+    fn output_offset(&self, input_size: (u32, u32), output_size: (u32, u32)) -> Option<(u32, u32)> {
+        if output_size.0 > input_size.0 || output_size.1 > input_size.1 {
+            return None;
+        }
+        Some((
+            (input_size.0 - output_size.0) / 2,
+            (input_size.1 - output_size.1) / 2,
+        ))
+    }
 }
 
+/// Read and write settings for `process_images`
+#[derive(Debug, Clone)]
+pub struct ProcessOptions {
+    read_options: ReadOptions,
+    output_options: OutputOptions,
+    directory_options: DirectoryOptions,
+    max_concurrency: usize,
+    continue_on_error: bool,
+    write_report: bool,
+    incremental: IncrementalMode,
+    statistics_format: StatisticsFormat,
+    progress: Option<ProgressHandler>,
+    cancellation: CancellationToken,
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            read_options: ReadOptions::default(),
+            output_options: OutputOptions::default(),
+            directory_options: DirectoryOptions::default(),
+            max_concurrency: std::thread::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1),
+            continue_on_error: false,
+            write_report: false,
+            incremental: IncrementalMode::Off,
+            statistics_format: StatisticsFormat::Ascii,
+            progress: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+}
+
+impl ProcessOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_read_options(mut self, read_options: ReadOptions) -> Self {
+        self.read_options = read_options;
+        self
+    }
+
+    pub fn with_output_options(mut self, output_options: OutputOptions) -> Self {
+        self.output_options = output_options;
+        self
+    }
+
+    /// Which files of the input folder are processed, and whether subfolders are walked
+    pub fn with_directory_options(mut self, directory_options: DirectoryOptions) -> Self {
+        self.directory_options = directory_options;
+        self
+    }
+
+    /// Number of images processed at the same time, the number of CPU threads by default
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Keeps going when an image fails, listing it in the report instead of returning the error
+    pub fn with_continue_on_error(mut self, continue_on_error: bool) -> Self {
+        self.continue_on_error = continue_on_error;
+        self
+    }
+
+    /// Writes the report as `report.json` into the output folder
+    pub fn with_write_report(mut self, write_report: bool) -> Self {
+        self.write_report = write_report;
+        self
+    }
+
+    /// Skips images whose output is up to date, listing them as skipped in the report
+    pub fn with_incremental(mut self, incremental: IncrementalMode) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// How the statistics of each image are printed when `print_stats` is set, ASCII by default
+    pub fn with_statistics_format(mut self, statistics_format: StatisticsFormat) -> Self {
+        self.statistics_format = statistics_format;
+        self
+    }
+
+    /// Receives the progress of the run
+    pub fn with_progress(mut self, progress: ProgressHandler) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Token to stop the run with, keep a clone of it to cancel
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn get_read_options(&self) -> &ReadOptions {
+        &self.read_options
+    }
+
+    pub fn get_output_options(&self) -> &OutputOptions {
+        &self.output_options
+    }
+
+    pub fn get_directory_options(&self) -> &DirectoryOptions {
+        &self.directory_options
+    }
+
+    pub fn get_max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    pub fn get_cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    fn report_progress(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress.report(event);
+        }
+    }
+}
+
+/// Processes the image at `path`, or the images in `dir`, with at most
+/// `max_concurrency` images in flight. Files are read on the async runtime, while decoding,
+/// the operations, statistics and encoding run on the blocking thread pool.
+/// Stops at the first image that fails, unless the options continue on errors,
+/// in which case the failures are listed in the returned report.
+/// A cancelled run returns the report so far, with the images left out listed as skipped.
+/// With an incremental mode, images with an up to date output are skipped as unchanged.
+/// Progress and timings are logged through the `log` crate, which stays silent without a logger.
 pub async fn process_images<T>(
     is_dir: bool,
     dir: Option<&str>,
     dir_out: &str,
     path: Option<&str>,
-    operations: &Vec<Box<dyn Operation<T>>>, //clippy complains: change this to: `&[Box<dyn Operation<T>>], because writing `&Vec` instead of `&[_]` involves a new object where a slice will do
+    operations: Arc<Vec<Box<dyn Operation<T>>>>,
     print_stats: bool,
-) -> Result<(), Box<dyn std::error::Error>>
+    options: &ProcessOptions,
+) -> Result<BatchReport, Box<dyn std::error::Error>>
 where
-    T: Copy
-        + Clone
-        + From<u8>
-        + std::cmp::PartialEq
-        + Sync
-        + Send
-        + Into<u8>
-        + Into<f64>
-        + Hash
-        + Eq
-        + Debug
-        + Ord,
+    T: Sample,
 {
+    let started = Instant::now();
+    let mut jobs: Vec<(PathBuf, String)> = Vec::new();
+    let mut skipped: Vec<SkippedFile> = Vec::new();
     match is_dir {
         true => {
             if dir.is_none() {
                 return Err("DIR can not be None! for directory scan".into());
             }
-            let mut dir_entries = fs::read_dir(dir.unwrap()).await?;
-            while let Some(entry) = dir_entries.next_entry().await? {
-                let img_path = entry.path();
-                let image = ImageReader::open(img_path.clone())?.decode()?;
-
-                println!(
-                    "Last pixel in image is: {:?}",
-                    image
-                        .get_pixel(image.width() - 1, image.height() - 1)
-                        .channels()
-                        .get(2)
-                );
-
-                let final_image =
-                    computation_image_processing(img_path.clone(), operations).await?;
-
-                if print_stats {
-                    print_statistics(&final_image).await;
-                }
-
-                let mut out_file_name = Path::new(img_path.to_str().unwrap())
-                    .file_stem()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .replace("\"", "");
-
-                out_file_name = String::from(dir_out) + &out_file_name + &String::from(".png");
-                println!("Writing to: {:?}", out_file_name);
-                image_writer((out_file_name).as_str(), &final_image)?;
+            let (images, skipped_files) =
+                find_images(dir.unwrap(), &options.directory_options).await?;
+            skipped = skipped_files;
+            for relative_path in images {
+                let out_file_name =
+                    output_file_name(dir_out, &relative_path, &options.output_options).await?;
+                jobs.push((Path::new(dir.unwrap()).join(&relative_path), out_file_name));
             }
         }
         false => {
             if path.is_none() {
                 return Err("PATH can not be None! for single image scan".into());
             }
-            let image = ImageReader::open(path.unwrap())?.decode()?;
-
-            println!(
-                "Last pixel in image is: {:?}",
-                image
-                    .get_pixel(image.width() - 1, image.height() - 1)
-                    .channels()
-                    .get(2)
-            );
+            let file_name = Path::new(path.unwrap()).file_name().unwrap_or_default();
+            let out_file_name =
+                output_file_name(dir_out, Path::new(file_name), &options.output_options).await?;
+            jobs.push((PathBuf::from(path.unwrap()), out_file_name));
+        }
+    }
+
+    run_jobs(
+        jobs,
+        skipped,
+        dir_out,
+        operations,
+        print_stats,
+        options,
+        started,
+    )
+    .await
+}
 
-            let final_image =
-                computation_image_processing(PathBuf::from(path.unwrap()), operations).await?;
+/// Processes the images at `relative_paths` in `dir`, like `process_images` does for the
+/// images it finds in a folder, mirroring the relative paths in `dir_out`
+pub async fn process_files<T>(
+    dir: &str,
+    relative_paths: &[PathBuf],
+    dir_out: &str,
+    operations: Arc<Vec<Box<dyn Operation<T>>>>,
+    print_stats: bool,
+    options: &ProcessOptions,
+) -> Result<BatchReport, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let started = Instant::now();
+    let mut jobs: Vec<(PathBuf, String)> = Vec::new();
+    for relative_path in relative_paths.iter() {
+        let out_file_name =
+            output_file_name(dir_out, relative_path, &options.output_options).await?;
+        jobs.push((Path::new(dir).join(relative_path), out_file_name));
+    }
+
+    run_jobs(
+        jobs,
+        Vec::new(),
+        dir_out,
+        operations,
+        print_stats,
+        options,
+        started,
+    )
+    .await
+}
 
-            if print_stats {
-                print_statistics(&final_image).await;
+/// Processes the images of `jobs`, pairs of input path and output path, into a report
+/// that also lists the files skipped before, and the time since `started`
+async fn run_jobs<T>(
+    jobs: Vec<(PathBuf, String)>,
+    mut skipped: Vec<SkippedFile>,
+    dir_out: &str,
+    operations: Arc<Vec<Box<dyn Operation<T>>>>,
+    print_stats: bool,
+    options: &ProcessOptions,
+    started: Instant,
+) -> Result<BatchReport, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let total = jobs.len();
+    options.report_progress(ProgressEvent::Started { total });
+
+    let cache_path = String::from(dir_out) + CACHE_FILE_NAME;
+    let mut cache = ProcessCache::new();
+    let mut fingerprint = 0;
+    if options.incremental == IncrementalMode::ContentHash {
+        cache = ProcessCache::load(&cache_path).await;
+        let settings = format!(
+            "{};{}",
+            options.read_options.fingerprint(),
+            options.output_options.fingerprint()
+        );
+        fingerprint = pipeline_fingerprint(&operations, &settings);
+    }
+    let mut updated_cache = cache.clone();
+    let cache = Arc::new(cache);
+
+    let mut processed: Vec<ProcessedFile> = Vec::new();
+    let mut failed: Vec<FailedFile> = Vec::new();
+    let mut cancelled: Vec<PathBuf> = Vec::new();
+    let mut done = 0;
+    let mut record =
+        |result: Result<FileOutcome, JoinError>| -> Result<(), Box<dyn std::error::Error>> {
+            let (img_path, out_file_name, duration, outcome) = result?;
+            done += 1;
+            options.report_progress(ProgressEvent::FileFinished {
+                path: img_path.clone(),
+                done,
+                total,
+                success: matches!(outcome, Ok(_) | Err(FileError::Unchanged)),
+            });
+            match outcome {
+                Ok(key) => {
+                    info!(
+                        "Processed {:?} into {:?} in {:?}",
+                        img_path, out_file_name, duration
+                    );
+                    if let Some(key) = key {
+                        updated_cache.insert(&out_file_name, key);
+                    }
+                    processed.push(ProcessedFile::new(img_path, out_file_name, duration));
+                }
+                Err(FileError::Unchanged) => {
+                    debug!("Skipping {:?}, {:?} is up to date", img_path, out_file_name);
+                    skipped.push(SkippedFile::new(img_path, "unchanged"));
+                }
+                Err(FileError::Cancelled) => cancelled.push(img_path),
+                Err(FileError::Failed(error)) if options.continue_on_error => {
+                    updated_cache.remove(&out_file_name);
+                    warn!(
+                        "Failed to process {:?} in {:?}: {}",
+                        img_path, duration, error
+                    );
+                    failed.push(FailedFile::new(img_path, error, duration));
+                }
+                Err(FileError::Failed(error)) => return Err(error.into()),
             }
 
-            let mut out_file_name = Path::new(path.unwrap())
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .replace("\"", "");
-            out_file_name = String::from(dir_out) + &out_file_name + &String::from(".png");
-            println!("Writing to: {:?}", out_file_name);
-            image_writer(&out_file_name, &final_image)?;
+            Ok(())
+        };
+
+    let in_flight = Arc::new(Semaphore::new(options.max_concurrency));
+    let mut tasks = JoinSet::new();
+    let mut not_started: Vec<PathBuf> = Vec::new();
+    let mut jobs = jobs.into_iter();
+    for (img_path, out_file_name) in jobs.by_ref() {
+        let permit = in_flight.clone().acquire_owned().await?;
+        while let Some(result) = tasks.try_join_next() {
+            record(result)?;
         }
+        if options.cancellation.is_cancelled() {
+            not_started.push(img_path);
+            break;
+        }
+
+        let operations = operations.clone();
+        let options = options.clone();
+        let cache = cache.clone();
+        tasks.spawn(async move {
+            let file_started = Instant::now();
+            // Runs as its own task, so a panic fails this file instead of the whole batch
+            let outcome = tokio::spawn(process_image_file(
+                img_path.clone(),
+                out_file_name.clone(),
+                operations,
+                print_stats,
+                options,
+                (cache, fingerprint),
+            ))
+            .await
+            .unwrap_or_else(|error| Err(FileError::Failed(format!("{:?}: {}", img_path, error))));
+            drop(permit);
+            (img_path, out_file_name, file_started.elapsed(), outcome)
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        record(result)?;
+    }
+    not_started.extend(jobs.map(|(img_path, _)| img_path));
+    if options.incremental == IncrementalMode::ContentHash {
+        updated_cache.save(&cache_path).await?;
+    }
+
+    let is_cancelled = options.cancellation.is_cancelled();
+    if is_cancelled {
+        warn!("The run was cancelled");
     }
+    options.report_progress(ProgressEvent::Finished {
+        done,
+        total,
+        cancelled: is_cancelled,
+    });
+    skipped.extend(
+        cancelled
+            .into_iter()
+            .chain(not_started)
+            .map(|img_path| SkippedFile::new(img_path, "cancelled")),
+    );
+    let report = BatchReport::new(processed, skipped, failed, started.elapsed())
+        .with_cancelled(is_cancelled);
+    info!(
+        "Processed {} image(s), skipped {} file(s), {} image(s) failed in {:?}",
+        report.get_processed().len(),
+        report.get_skipped().len(),
+        report.get_failed().len(),
+        report.get_duration()
+    );
+    if options.write_report {
+        let report_path = String::from(dir_out) + "report.json";
+        fs::write(&report_path, report.to_json()).await?;
+        info!("Saved report to {:?}", report_path);
+    }
+
+    Ok(report)
+}
+
+/// File in the output folder holding the cache keys of `IncrementalMode::ContentHash`
+const CACHE_FILE_NAME: &str = ".image_processor_cache";
 
-    Ok(())
+/// Why an image was not written
+enum FileError {
+    Failed(String),
+    Cancelled,
+    Unchanged,
 }
 
+/// Input path, output path, time taken and result of processing one image,
+/// with the cache key of the written output when caching by content
+type FileOutcome = (PathBuf, String, Duration, Result<Option<u64>, FileError>);
+
+/// Reads one image, then decodes, processes and writes it on the blocking thread pool
+async fn process_image_file<T>(
+    img_path: PathBuf,
+    out_file_name: String,
+    operations: Arc<Vec<Box<dyn Operation<T>>>>,
+    print_stats: bool,
+    options: ProcessOptions,
+    (cache, fingerprint): (Arc<ProcessCache>, u64),
+) -> Result<Option<u64>, FileError>
+where
+    T: Sample,
+{
+    if options.cancellation.is_cancelled() {
+        return Err(FileError::Cancelled);
+    }
+    if options.incremental == IncrementalMode::SkipNewerOutputs
+        && is_output_newer(&img_path, &out_file_name).await
+    {
+        return Err(FileError::Unchanged);
+    }
+    debug!("Reading image {:?}", img_path);
+    let image_bytes = fs::read(&img_path)
+        .await
+        .map_err(|error| FileError::Failed(format!("{:?}: {}", img_path, error)))?;
+
+    tokio::task::spawn_blocking(move || {
+        let key = (options.incremental == IncrementalMode::ContentHash)
+            .then(|| cache_key(&image_bytes, fingerprint));
+        if let Some(key) = key {
+            if cache.is_fresh(&out_file_name, key) && Path::new(&out_file_name).exists() {
+                return Err(FileError::Unchanged);
+            }
+        }
+
+        let (mut new_image, metadata): (Images<T>, ImageMetadata) =
+            decode_image(&image_bytes, None, &options.read_options)
+                .map_err(|error| FileError::Failed(format!("{:?}: {}", img_path, error)))?;
+        for (index, ops) in operations.iter().enumerate() {
+            if options.cancellation.is_cancelled() {
+                return Err(FileError::Cancelled);
+            }
+            options.report_progress(ProgressEvent::Operation {
+                path: img_path.clone(),
+                operation: index,
+                operations: operations.len(),
+            });
+            let operation_started = Instant::now();
+            ops.check_input_size((new_image.get_width(), new_image.get_height()))
+                .map_err(|error| FileError::Failed(format!("{:?}: {}", img_path, error)))?;
+            new_image = ops.apply(&new_image);
+            debug!(
+                "Applied operation {} of {} to {:?} in {:?}",
+                index + 1,
+                operations.len(),
+                img_path,
+                operation_started.elapsed()
+            );
+        }
+
+        if print_stats {
+            log_statistics(&new_image, options.statistics_format);
+        }
+
+        image_writer_with_options(
+            &out_file_name,
+            &new_image,
+            &options.output_options,
+            &metadata,
+        )
+        .map_err(|error| FileError::Failed(format!("{:?}: {}", out_file_name, error)))?;
+
+        Ok(key)
+    })
+    .await
+    .map_err(|error| FileError::Failed(error.to_string()))?
+}
+
+/// Whether the output exists and was modified after the input
+async fn is_output_newer(img_path: &Path, out_file_name: &str) -> bool {
+    let modified = |metadata: std::io::Result<std::fs::Metadata>| {
+        metadata.and_then(|metadata| metadata.modified()).ok()
+    };
+    let input_modified = modified(fs::metadata(img_path).await);
+    let output_modified = modified(fs::metadata(out_file_name).await);
+
+    match (input_modified, output_modified) {
+        (Some(input_modified), Some(output_modified)) => output_modified >= input_modified,
+        _ => false,
+    }
+}
+
+/// Output path in `dir_out` mirroring `relative_path`, the path of the image relative to the
+/// input folder, and keeping its extension unless an output format is chosen.
+/// Creates the subfolders of the output path.
+async fn output_file_name(
+    dir_out: &str,
+    relative_path: &Path,
+    output_options: &OutputOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let out_file_name = relative_path
+        .file_stem()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .replace("\"", "");
+    let out_path = relative_path
+        .with_file_name(out_file_name + "." + &output_options.extension_for(relative_path));
+
+    let out_file_name = String::from(dir_out) + out_path.to_str().unwrap();
+    if let Some(parent) = Path::new(&out_file_name).parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    Ok(out_file_name)
+}
+
+/// Reads the image, applies the operations in order, and returns the result with the source metadata
 pub async fn computation_image_processing<T>(
     img_path: PathBuf,
-    operations: &Vec<Box<dyn Operation<T>>>,
-) -> Result<Images<T>, Box<dyn std::error::Error>>
+    operations: &[Box<dyn Operation<T>>],
+    read_options: &ReadOptions,
+) -> Result<(Images<T>, ImageMetadata), Box<dyn std::error::Error>>
 where
-    T: Copy
-        + Clone
-        + From<u8>
-        + std::cmp::PartialEq
-        + Send
-        + Sync
-        + Into<u8>
-        + Into<f64>
-        + Hash
-        + Eq
-        + Debug
-        + Ord,
+    T: Sample,
 {
-    let image_read: Images<T> = image_reader(img_path.to_str().unwrap()).await?;
+    let (image_read, metadata): (Images<T>, ImageMetadata) =
+        image_reader_with_options(img_path.to_str().unwrap(), read_options).await?;
 
     let mut new_image: Images<T> = Images::new(
         image_read.get_width(),
@@ -149,29 +615,20 @@ where
         new_image = ops.apply(&new_image);
     }
 
-    Ok(new_image)
+    Ok((new_image, metadata))
 }
 
+/// Logs the ASCII statistics report of the image at info level
 pub async fn print_statistics<T>(image: &Images<T>)
 where
-    T: Copy
-        + Clone
-        + From<u8>
-        + std::cmp::PartialEq
-        + Send
-        + Sync
-        + Into<f64>
-        + Hash
-        + Eq
-        + Debug
-        + Ord,
+    T: Sample,
 {
-    let histogram_stats = compute_histogram(image);
-    print_histogram(histogram_stats);
-
-    let mean = compute_mean(image);
-    println!("Mean for the resized image: {:?}", mean);
+    log_statistics(image, StatisticsFormat::Ascii);
+}
 
-    let variance = compute_variance(image);
-    println!("Variance for the resized image: {:?}", variance);
+fn log_statistics<T>(image: &Images<T>, format: StatisticsFormat)
+where
+    T: Sample,
+{
+    info!("{}", ImageStatistics::new(image).format(format).trim_end());
 }
@@ -0,0 +1,210 @@
+use crate::{
+    filters::{
+        blur::{Blur, SmoothingKernelChoices},
+        color_adjustment::{HueShift, Saturation, Vibrance},
+        edge_detection::{EdgeDetectingKernelChoices, EdgeDetection},
+        gamma_correction::GammaCorrection,
+        gray_scale::{GrayScale, GrayScaleAlgorithms},
+        morphological::{Dilation, Erosion, MorphologicalKernelChoices},
+        sharpen::{Sharpen, SharpeningKernelChoices},
+    },
+    transformations::{
+        crop::Crop,
+        resize::{ResizeBilinearInterpolation, ResizeNearestNeighbour},
+        rotate::{Flip90Left, Flip90Right, FlipHorizontal, FlipVertical},
+    },
+};
+
+use super::{operations::Operation, sample::Sample};
+
+/// Operations run in order over an image
+pub type Pipeline<T> = Vec<Box<dyn Operation<T>>>;
+
+/// Builds the operations of a text description, such as `resize:256,256;blur:gaussian;gamma:1.5`.
+/// Operations are separated by `;` and their arguments by `,`, after a `:`:
+///
+/// - `flip_horizontal`, `flip_vertical`, `rotate_left`, `rotate_right`
+/// - `resize:width,height` (nearest neighbour), `resize_bilinear:width,height`
+/// - `crop:x,y,width,height`
+/// - `grayscale:average|luminosity`
+/// - `blur:gaussian|box`
+/// - `sharpen:basic|high_pass|edge_enhancement`
+/// - `edge_detection:outline|sobel_x|sobel_y|emboss`
+/// - `erosion:kernel`, `dilation:kernel`, with a kernel of
+///   `cross|diamond|horizontal|vertical|diagonal|diagonal2`
+/// - `gamma:gamma`, `hue:degrees`, `saturation:factor`, `vibrance:amount`
+///
+/// Sizes above `max_dimension` are rejected, so untrusted descriptions can not ask for huge images.
+pub fn parse_pipeline<T>(
+    description: &str,
+    max_dimension: u32,
+) -> Result<Pipeline<T>, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    description
+        .split(';')
+        .map(str::trim)
+        .filter(|step| !step.is_empty())
+        .map(|step| parse_operation(step, max_dimension))
+        .collect()
+}
+
+fn parse_operation<T>(
+    step: &str,
+    max_dimension: u32,
+) -> Result<Box<dyn Operation<T>>, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let (name, arguments) = step.split_once(':').unwrap_or((step, ""));
+    let arguments: Vec<&str> = arguments
+        .split(',')
+        .map(str::trim)
+        .filter(|argument| !argument.is_empty())
+        .collect();
+    let expect_arguments = |count: usize| -> Result<(), Box<dyn std::error::Error>> {
+        if arguments.len() != count {
+            return Err(format!(
+                "{:?} takes {} argument(s), got {}",
+                name,
+                count,
+                arguments.len()
+            )
+            .into());
+        }
+        Ok(())
+    };
+    let size = |index: usize| -> Result<u32, Box<dyn std::error::Error>> {
+        let size: u32 = arguments[index]
+            .parse()
+            .map_err(|_| format!("{:?} is not a size in {:?}", arguments[index], step))?;
+        if size > max_dimension {
+            return Err(format!(
+                "{} in {:?} is above the limit of {}",
+                size, step, max_dimension
+            )
+            .into());
+        }
+        Ok(size)
+    };
+    let number = |index: usize| -> Result<f64, Box<dyn std::error::Error>> {
+        let number: f64 = arguments[index]
+            .parse()
+            .map_err(|_| format!("{:?} is not a number in {:?}", arguments[index], step))?;
+        if !number.is_finite() {
+            return Err(format!(
+                "{:?} is not a finite number in {:?}",
+                arguments[index], step
+            )
+            .into());
+        }
+        Ok(number)
+    };
+    let unknown_choice = || format!("Unknown choice {:?} in {:?}", arguments[0], step);
+
+    let operation: Box<dyn Operation<T>> = match name.trim() {
+        "flip_horizontal" => {
+            expect_arguments(0)?;
+            Box::new(FlipHorizontal::new())
+        }
+        "flip_vertical" => {
+            expect_arguments(0)?;
+            Box::new(FlipVertical::new())
+        }
+        "rotate_left" => {
+            expect_arguments(0)?;
+            Box::new(Flip90Left::new())
+        }
+        "rotate_right" => {
+            expect_arguments(0)?;
+            Box::new(Flip90Right::new())
+        }
+        "resize" => {
+            expect_arguments(2)?;
+            Box::new(ResizeNearestNeighbour::new(size(0)?, size(1)?))
+        }
+        "resize_bilinear" => {
+            expect_arguments(2)?;
+            Box::new(ResizeBilinearInterpolation::new(size(0)?, size(1)?))
+        }
+        "crop" => {
+            expect_arguments(4)?;
+            Box::new(Crop::new((size(0)?, size(1)?), size(2)?, size(3)?))
+        }
+        "grayscale" => {
+            expect_arguments(1)?;
+            let algorithm = match arguments[0] {
+                "average" => GrayScaleAlgorithms::Average,
+                "luminosity" => GrayScaleAlgorithms::Luminosity,
+                _ => return Err(unknown_choice().into()),
+            };
+            Box::new(GrayScale::new(algorithm))
+        }
+        "blur" => {
+            expect_arguments(1)?;
+            let kernel = match arguments[0] {
+                "gaussian" => SmoothingKernelChoices::Gaussian,
+                "box" => SmoothingKernelChoices::BoxBlur,
+                _ => return Err(unknown_choice().into()),
+            };
+            Box::new(Blur::new(kernel))
+        }
+        "sharpen" => {
+            expect_arguments(1)?;
+            let kernel = match arguments[0] {
+                "basic" => SharpeningKernelChoices::Basic,
+                "high_pass" => SharpeningKernelChoices::HighPass,
+                "edge_enhancement" => SharpeningKernelChoices::EdgeEnhancement,
+                _ => return Err(unknown_choice().into()),
+            };
+            Box::new(Sharpen::new(kernel))
+        }
+        "edge_detection" => {
+            expect_arguments(1)?;
+            let kernel = match arguments[0] {
+                "outline" => EdgeDetectingKernelChoices::Outline,
+                "sobel_x" => EdgeDetectingKernelChoices::SobelX,
+                "sobel_y" => EdgeDetectingKernelChoices::SobelY,
+                "emboss" => EdgeDetectingKernelChoices::Emboss,
+                _ => return Err(unknown_choice().into()),
+            };
+            Box::new(EdgeDetection::new(kernel))
+        }
+        "erosion" | "dilation" => {
+            expect_arguments(1)?;
+            let kernel = match arguments[0] {
+                "cross" => MorphologicalKernelChoices::Cross,
+                "diamond" => MorphologicalKernelChoices::Diamond,
+                "horizontal" => MorphologicalKernelChoices::Horizontal,
+                "vertical" => MorphologicalKernelChoices::Vertical,
+                "diagonal" => MorphologicalKernelChoices::Diagonal,
+                "diagonal2" => MorphologicalKernelChoices::Diagonal2,
+                _ => return Err(unknown_choice().into()),
+            };
+            match name.trim() {
+                "erosion" => Box::new(Erosion::new(kernel)),
+                _ => Box::new(Dilation::new(kernel)),
+            }
+        }
+        "gamma" => {
+            expect_arguments(1)?;
+            Box::new(GammaCorrection::new(number(0)?))
+        }
+        "hue" => {
+            expect_arguments(1)?;
+            Box::new(HueShift::new(number(0)?))
+        }
+        "saturation" => {
+            expect_arguments(1)?;
+            Box::new(Saturation::new(number(0)?))
+        }
+        "vibrance" => {
+            expect_arguments(1)?;
+            Box::new(Vibrance::new(number(0)?))
+        }
+        _ => return Err(format!("Unknown operation {:?}", name).into()),
+    };
+
+    Ok(operation)
+}
@@ -1,5 +1,7 @@
 use std::ops::{Add, Div, Mul, Sub};
 
+use super::sample::{RoundingMode, Sample};
+
 #[derive(Debug, Clone)]
 pub struct Pixels<T>
 where
@@ -43,122 +45,129 @@ where
 
 impl<T> Default for Pixels<T>
 where
-    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+    T: Sample,
 {
     fn default() -> Self {
         Self {
-            red: 0.into(),
-            green: 0.into(),
-            blue: 0.into(),
-            alpha: 255.into(),
+            red: T::MIN_VALUE,
+            green: T::MIN_VALUE,
+            blue: T::MIN_VALUE,
+            alpha: T::MAX_VALUE,
+        }
+    }
+}
+
+impl<T> Pixels<T>
+where
+    T: Sample,
+{
+    /// Applies `op` to every channel in f64, clamping the result to the range of `T`
+    fn map_channels<F>(self, op: F) -> Self
+    where
+        F: Fn(f64) -> f64,
+    {
+        self.map_channels_rounded(RoundingMode::Truncate, op)
+    }
+
+    fn map_channels_rounded<F>(self, rounding: RoundingMode, op: F) -> Self
+    where
+        F: Fn(f64) -> f64,
+    {
+        Self {
+            red: T::from_f64_rounded(op(self.red.to_f64()), rounding),
+            green: T::from_f64_rounded(op(self.green.to_f64()), rounding),
+            blue: T::from_f64_rounded(op(self.blue.to_f64()), rounding),
+            alpha: T::from_f64_rounded(op(self.alpha.to_f64()), rounding),
+        }
+    }
+
+    /// Multiplies every channel by `factor`, saturating, and rounds with `rounding`
+    pub fn scale(self, factor: f64, rounding: RoundingMode) -> Self {
+        self.map_channels_rounded(rounding, |value| value * factor)
+    }
+
+    /// Divides every channel by `divisor`, rounding with `rounding`, or `None` when it is zero
+    pub fn checked_div(self, divisor: u8, rounding: RoundingMode) -> Option<Self> {
+        if divisor == 0 {
+            return None;
+        }
+
+        Some(self.map_channels_rounded(rounding, |value| value / divisor as f64))
+    }
+
+    /// Applies `op` to every pair of channels in f64, clamping the result to the range of `T`
+    fn zip_channels<F>(self, rhs: Self, op: F) -> Self
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        Self {
+            red: T::from_f64(op(self.red.to_f64(), rhs.red.to_f64())),
+            green: T::from_f64(op(self.green.to_f64(), rhs.green.to_f64())),
+            blue: T::from_f64(op(self.blue.to_f64(), rhs.blue.to_f64())),
+            alpha: T::from_f64(op(self.alpha.to_f64(), rhs.alpha.to_f64())),
         }
     }
 }
 
 impl<T> Add for Pixels<T>
 where
-    T: Copy + Clone + From<u8> + Into<f64> + std::cmp::PartialEq,
+    T: Sample,
 {
     type Output = Self;
     fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            red: ((self.red.into() + rhs.red.into()) as u8)
-                .clamp(0, 255)
-                .into(),
-            green: ((self.green.into() + rhs.green.into()) as u8)
-                .clamp(0, 255)
-                .into(),
-            blue: ((self.blue.into() + rhs.blue.into()) as u8)
-                .clamp(0, 255)
-                .into(),
-            alpha: ((self.alpha.into() + rhs.alpha.into()) as u8)
-                .clamp(0, 255)
-                .into(),
-        }
+        self.zip_channels(rhs, |lhs, rhs| lhs + rhs)
     }
 }
 
 impl<T> Sub for Pixels<T>
 where
-    T: Copy + Clone + From<u8> + Into<f64> + std::cmp::PartialEq,
+    T: Sample,
 {
     type Output = Self;
     fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            red: ((self.red.into() - rhs.red.into()) as u8)
-                .clamp(0, 255)
-                .into(),
-            green: ((self.green.into() - rhs.green.into()) as u8)
-                .clamp(0, 255)
-                .into(),
-            blue: ((self.blue.into() - rhs.blue.into()) as u8)
-                .clamp(0, 255)
-                .into(),
-            alpha: ((self.alpha.into() - rhs.alpha.into()) as u8)
-                .clamp(0, 255)
-                .into(),
-        }
+        self.zip_channels(rhs, |lhs, rhs| lhs - rhs)
     }
 }
 
 impl<T> Mul<f64> for Pixels<T>
 where
-    T: Copy + Clone + From<u8> + Into<f64> + std::cmp::PartialEq,
+    T: Sample,
 {
     type Output = Self;
     fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            red: ((self.red.into() * rhs) as u8).clamp(0, 255).into(),
-            green: ((self.green.into() * rhs) as u8)
-                .clamp(0, 255)
-                .into(),
-            blue: ((self.blue.into() * rhs) as u8).clamp(0, 255).into(),
-            alpha: ((self.alpha.into() * rhs) as u8)
-                .clamp(0, 255)
-                .into(),
-        }
+        self.map_channels(|lhs| lhs * rhs)
     }
 }
 
 impl<T> Mul<u32> for Pixels<T>
 where
-    T: Copy + Clone + From<u8> + Into<u32> + std::cmp::PartialEq,
+    T: Sample,
 {
     type Output = Self;
     fn mul(self, rhs: u32) -> Self::Output {
-        Self {
-            red: ((self.red.into() * rhs) as u8).clamp(0, 255).into(),
-            green: ((self.green.into() * rhs) as u8)
-                .clamp(0, 255)
-                .into(),
-            blue: ((self.blue.into() * rhs) as u8).clamp(0, 255).into(),
-            alpha: ((self.alpha.into() * rhs) as u8)
-                .clamp(0, 255)
-                .into(),
-        }
+        self.map_channels(|lhs| lhs * rhs as f64)
     }
 }
 
+/// Dividing by zero saturates: non zero channels become `T::MAX_VALUE`, zero channels stay zero.
+/// Use `checked_div` to handle a zero divisor explicitly.
 impl<T> Div<u8> for Pixels<T>
 where
-    T: Copy + Clone + From<u8> + Into<u32> + std::cmp::PartialEq,
+    T: Sample,
 {
     type Output = Self;
     fn div(self, rhs: u8) -> Self::Output {
-        Self {
-            red: ((self.red.into() / rhs as u32) as u8)
-                .clamp(0, 255)
-                .into(),
-            green: ((self.green.into() / rhs as u32) as u8)
-                .clamp(0, 255)
-                .into(),
-            blue: ((self.blue.into() / rhs as u32) as u8)
-                .clamp(0, 255)
-                .into(),
-            alpha: ((self.alpha.into() / rhs as u32) as u8)
-                .clamp(0, 255)
-                .into(),
+        if rhs == 0 {
+            return self.map_channels(|lhs| {
+                if lhs > T::MIN_VALUE.to_f64() {
+                    T::MAX_VALUE.to_f64()
+                } else {
+                    T::MIN_VALUE.to_f64()
+                }
+            });
         }
+
+        self.map_channels(|lhs| lhs / rhs as f64)
     }
 }
 
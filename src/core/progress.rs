@@ -0,0 +1,91 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Progress of a `process_images` run
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// The images to process were found
+    Started { total: usize },
+    /// The operation at index `operation` of `operations` starts on the image at `path`
+    Operation {
+        path: PathBuf,
+        operation: usize,
+        operations: usize,
+    },
+    /// The image at `path` is done, written or not, and `done` images of `total` are done
+    FileFinished {
+        path: PathBuf,
+        done: usize,
+        total: usize,
+        success: bool,
+    },
+    /// The run is over, after `done` images of `total`
+    Finished {
+        done: usize,
+        total: usize,
+        cancelled: bool,
+    },
+}
+
+/// Receives the progress events of a run. Events come from the async runtime and from the
+/// blocking threads processing the images, so the handler should return quickly.
+#[derive(Clone)]
+pub struct ProgressHandler {
+    handler: Arc<dyn Fn(ProgressEvent) + Send + Sync>,
+}
+
+impl ProgressHandler {
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(ProgressEvent) + Send + Sync + 'static,
+    {
+        Self {
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Sends the events into a channel, dropping them once the receiver is closed
+    pub fn from_channel(sender: UnboundedSender<ProgressEvent>) -> Self {
+        Self::new(move |event| {
+            let _ = sender.send(event);
+        })
+    }
+
+    pub fn report(&self, event: ProgressEvent) {
+        (self.handler)(event);
+    }
+}
+
+impl std::fmt::Debug for ProgressHandler {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("ProgressHandler")
+    }
+}
+
+/// Stops a run from another task or thread. Clones share the same state.
+/// Images not started yet are skipped, and images in flight stop before their next operation.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,200 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::utils::json::{json_number, json_string};
+
+/// An image `process_images` wrote
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessedFile {
+    input: PathBuf,
+    output: String,
+    duration: Duration,
+}
+
+impl ProcessedFile {
+    pub fn new(input: PathBuf, output: String, duration: Duration) -> Self {
+        Self {
+            input,
+            output,
+            duration,
+        }
+    }
+
+    pub fn get_input(&self) -> &PathBuf {
+        &self.input
+    }
+
+    pub fn get_output(&self) -> &str {
+        &self.output
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// A file of the input folder that was left out, such as a file that is not an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedFile {
+    path: PathBuf,
+    reason: String,
+}
+
+impl SkippedFile {
+    pub fn new(path: PathBuf, reason: &str) -> Self {
+        Self {
+            path,
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn get_path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn get_reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+/// An image that could not be read, processed or written
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedFile {
+    input: PathBuf,
+    reason: String,
+    duration: Duration,
+}
+
+impl FailedFile {
+    pub fn new(input: PathBuf, reason: String, duration: Duration) -> Self {
+        Self {
+            input,
+            reason,
+            duration,
+        }
+    }
+
+    pub fn get_input(&self) -> &PathBuf {
+        &self.input
+    }
+
+    pub fn get_reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// Summary of a `process_images` run, with the files sorted by path
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchReport {
+    processed: Vec<ProcessedFile>,
+    skipped: Vec<SkippedFile>,
+    failed: Vec<FailedFile>,
+    duration: Duration,
+    cancelled: bool,
+}
+
+impl BatchReport {
+    pub fn new(
+        mut processed: Vec<ProcessedFile>,
+        mut skipped: Vec<SkippedFile>,
+        mut failed: Vec<FailedFile>,
+        duration: Duration,
+    ) -> Self {
+        processed.sort_by(|lhs, rhs| lhs.input.cmp(&rhs.input));
+        skipped.sort_by(|lhs, rhs| lhs.path.cmp(&rhs.path));
+        failed.sort_by(|lhs, rhs| lhs.input.cmp(&rhs.input));
+
+        Self {
+            processed,
+            skipped,
+            failed,
+            duration,
+            cancelled: false,
+        }
+    }
+
+    pub fn with_cancelled(mut self, cancelled: bool) -> Self {
+        self.cancelled = cancelled;
+        self
+    }
+
+    pub fn get_processed(&self) -> &[ProcessedFile] {
+        &self.processed
+    }
+
+    pub fn get_skipped(&self) -> &[SkippedFile] {
+        &self.skipped
+    }
+
+    pub fn get_failed(&self) -> &[FailedFile] {
+        &self.failed
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Whether the run was cancelled before every image was processed
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Whether every image was processed
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && !self.cancelled
+    }
+
+    /// The report as JSON, with durations in milliseconds
+    pub fn to_json(&self) -> String {
+        let milliseconds = |duration: Duration| json_number(duration.as_secs_f64() * 1000.0);
+        let path = |path: &PathBuf| json_string(&path.to_string_lossy());
+
+        let processed: Vec<String> = self
+            .processed
+            .iter()
+            .map(|file| {
+                format!(
+                    "{{\"input\": {}, \"output\": {}, \"duration_ms\": {}}}",
+                    path(&file.input),
+                    json_string(&file.output),
+                    milliseconds(file.duration)
+                )
+            })
+            .collect();
+        let skipped: Vec<String> = self
+            .skipped
+            .iter()
+            .map(|file| {
+                format!(
+                    "{{\"path\": {}, \"reason\": {}}}",
+                    path(&file.path),
+                    json_string(&file.reason)
+                )
+            })
+            .collect();
+        let failed: Vec<String> = self
+            .failed
+            .iter()
+            .map(|file| {
+                format!(
+                    "{{\"input\": {}, \"reason\": {}, \"duration_ms\": {}}}",
+                    path(&file.input),
+                    json_string(&file.reason),
+                    milliseconds(file.duration)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\n  \"processed\": [{}],\n  \"skipped\": [{}],\n  \"failed\": [{}],\n  \"duration_ms\": {},\n  \"cancelled\": {}\n}}\n",
+            processed.join(", "),
+            skipped.join(", "),
+            failed.join(", "),
+            milliseconds(self.duration),
+            self.cancelled
+        )
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt::Debug;
+
+use image::{DynamicImage, ImageBuffer, Pixel};
+
+use super::image::Images;
+
+/// Storage format of a sample type, used to pick the matching buffer when reading and writing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    U8,
+    U16,
+    F32,
+}
+
+/// How a computed value is brought back onto the integer sample grid.
+/// Floating point samples are never rounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// Drops the fractional part, the behavior of a plain cast
+    #[default]
+    Truncate,
+    /// Rounds half away from zero
+    Nearest,
+    Floor,
+    Ceil,
+}
+
+impl RoundingMode {
+    pub fn round(self, value: f64) -> f64 {
+        match self {
+            RoundingMode::Truncate => value.trunc(),
+            RoundingMode::Nearest => value.round(),
+            RoundingMode::Floor => value.floor(),
+            RoundingMode::Ceil => value.ceil(),
+        }
+    }
+}
+
+/// A single channel value of a pixel.
+/// Integer samples use their full range (0..=255 for u8, 0..=65535 for u16),
+/// floating point samples are normalized to 0.0..=1.0.
+pub trait Sample:
+    Copy + Clone + From<u8> + std::cmp::PartialEq + PartialOrd + Debug + Send + Sync + 'static
+{
+    const MIN_VALUE: Self;
+    const MAX_VALUE: Self;
+    const FORMAT: SampleFormat;
+
+    fn to_f64(self) -> f64;
+
+    /// Converts a value on the scale of this sample type, saturating it to the valid range.
+    /// Integer samples truncate the fractional part, and NaN becomes `MIN_VALUE`.
+    fn from_f64(value: f64) -> Self;
+
+    /// Same as `from_f64`, rounding integer samples with `rounding` instead of truncating
+    fn from_f64_rounded(value: f64, rounding: RoundingMode) -> Self {
+        match Self::FORMAT {
+            SampleFormat::F32 => Self::from_f64(value),
+            SampleFormat::U8 | SampleFormat::U16 => Self::from_f64(rounding.round(value)),
+        }
+    }
+
+    /// Value rescaled to 0.0..=1.0
+    fn to_unit(self) -> f64 {
+        let min_val = Self::MIN_VALUE.to_f64();
+        (self.to_f64() - min_val) / (Self::MAX_VALUE.to_f64() - min_val)
+    }
+
+    /// Converts a value in 0.0..=1.0, rounding to the nearest integer sample
+    fn from_unit(value: f64) -> Self;
+
+    /// Converts a decoded image to this sample type, taking over its buffer
+    /// when it already has this bit depth
+    fn from_dynamic_image(image: DynamicImage) -> Images<Self>;
+
+    /// Hands the sample buffer over to a `DynamicImage` of this bit depth,
+    /// or `None` when the buffer does not match the image dimensions
+    fn into_dynamic_image(image: Images<Self>) -> Option<DynamicImage>;
+}
+
+/// Takes over the sample buffer of `image` as an `ImageBuffer` with pixel type `P`
+fn into_buffer<P>(image: Images<P::Subpixel>) -> Option<ImageBuffer<P, Vec<P::Subpixel>>>
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    let (width, height) = (image.get_width(), image.get_height());
+    ImageBuffer::from_raw(width, height, image.into_samples())
+}
+
+/// Keeps, for every pixel of `stride` samples, the samples at the indices in `keep`
+fn select_samples(samples: &[f32], stride: usize, keep: &[usize]) -> Vec<f32> {
+    samples
+        .chunks_exact(stride)
+        .flat_map(|pixel| keep.iter().map(|index| pixel[*index]))
+        .collect()
+}
+
+impl Sample for u8 {
+    const MIN_VALUE: Self = 0;
+    const MAX_VALUE: Self = u8::MAX;
+    const FORMAT: SampleFormat = SampleFormat::U8;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_f64(value: f64) -> Self {
+        // Float to integer casts saturate, and NaN becomes 0
+        value as u8
+    }
+
+    fn from_unit(value: f64) -> Self {
+        (value * u8::MAX as f64).round() as u8
+    }
+
+    fn from_dynamic_image(image: DynamicImage) -> Images<Self> {
+        match image {
+            DynamicImage::ImageLuma8(buffer) => buffer.into(),
+            DynamicImage::ImageLumaA8(buffer) => buffer.into(),
+            DynamicImage::ImageRgb8(buffer) => buffer.into(),
+            DynamicImage::ImageRgba8(buffer) => buffer.into(),
+            other => match other.color().channel_count() {
+                1 => other.to_luma8().into(),
+                2 => other.to_luma_alpha8().into(),
+                3 => other.to_rgb8().into(),
+                _ => other.to_rgba8().into(),
+            },
+        }
+    }
+
+    fn into_dynamic_image(image: Images<Self>) -> Option<DynamicImage> {
+        Some(match image.get_channels() {
+            1 => DynamicImage::ImageLuma8(into_buffer(image)?),
+            2 => DynamicImage::ImageLumaA8(into_buffer(image)?),
+            3 => DynamicImage::ImageRgb8(into_buffer(image)?),
+            _ => DynamicImage::ImageRgba8(into_buffer(image)?),
+        })
+    }
+}
+
+impl Sample for u16 {
+    const MIN_VALUE: Self = 0;
+    const MAX_VALUE: Self = u16::MAX;
+    const FORMAT: SampleFormat = SampleFormat::U16;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value as u16
+    }
+
+    fn from_unit(value: f64) -> Self {
+        (value * u16::MAX as f64).round() as u16
+    }
+
+    fn from_dynamic_image(image: DynamicImage) -> Images<Self> {
+        match image {
+            DynamicImage::ImageLuma16(buffer) => buffer.into(),
+            DynamicImage::ImageLumaA16(buffer) => buffer.into(),
+            DynamicImage::ImageRgb16(buffer) => buffer.into(),
+            DynamicImage::ImageRgba16(buffer) => buffer.into(),
+            other => match other.color().channel_count() {
+                1 => other.to_luma16().into(),
+                2 => other.to_luma_alpha16().into(),
+                3 => other.to_rgb16().into(),
+                _ => other.to_rgba16().into(),
+            },
+        }
+    }
+
+    fn into_dynamic_image(image: Images<Self>) -> Option<DynamicImage> {
+        Some(match image.get_channels() {
+            1 => DynamicImage::ImageLuma16(into_buffer(image)?),
+            2 => DynamicImage::ImageLumaA16(into_buffer(image)?),
+            3 => DynamicImage::ImageRgb16(into_buffer(image)?),
+            _ => DynamicImage::ImageRgba16(into_buffer(image)?),
+        })
+    }
+}
+
+impl Sample for f32 {
+    const MIN_VALUE: Self = 0.0;
+    const MAX_VALUE: Self = 1.0;
+    const FORMAT: SampleFormat = SampleFormat::F32;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_f64(value: f64) -> Self {
+        if value.is_nan() {
+            return 0.0;
+        }
+        value.clamp(0.0, 1.0) as f32
+    }
+
+    fn from_unit(value: f64) -> Self {
+        Self::from_f64(value)
+    }
+
+    /// There is no float gray buffer, gray is taken from the red channel
+    fn from_dynamic_image(image: DynamicImage) -> Images<Self> {
+        match image {
+            DynamicImage::ImageRgb32F(buffer) => buffer.into(),
+            DynamicImage::ImageRgba32F(buffer) => buffer.into(),
+            other => {
+                let (width, height) = (other.width(), other.height());
+                match other.color().channel_count() {
+                    1 => Images::from_samples(
+                        width,
+                        height,
+                        1,
+                        select_samples(&other.to_rgb32f(), 3, &[0]),
+                    ),
+                    2 => Images::from_samples(
+                        width,
+                        height,
+                        2,
+                        select_samples(&other.to_rgba32f(), 4, &[0, 3]),
+                    ),
+                    3 => other.to_rgb32f().into(),
+                    _ => other.to_rgba32f().into(),
+                }
+            }
+        }
+    }
+
+    /// Gray images are expanded to RGB, as there is no float gray buffer
+    fn into_dynamic_image(image: Images<Self>) -> Option<DynamicImage> {
+        let (width, height) = (image.get_width(), image.get_height());
+        Some(match image.get_channels() {
+            1 => DynamicImage::ImageRgb32F(ImageBuffer::from_raw(
+                width,
+                height,
+                select_samples(image.get_samples(), 1, &[0, 0, 0]),
+            )?),
+            2 => DynamicImage::ImageRgba32F(ImageBuffer::from_raw(
+                width,
+                height,
+                select_samples(image.get_samples(), 2, &[0, 0, 0, 1]),
+            )?),
+            3 => DynamicImage::ImageRgb32F(into_buffer(image)?),
+            _ => DynamicImage::ImageRgba32F(into_buffer(image)?),
+        })
+    }
+}
@@ -0,0 +1,309 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use super::{image::Images, operations::Operation, sample::Sample};
+
+/// Size of the tiles `process_tiled` works on, 512 x 512 by default.
+/// Only one band of tiles, plus its halo, is held in memory at a time.
+#[derive(Debug, Clone, Copy)]
+pub struct TileOptions {
+    tile_width: u32,
+    tile_height: u32,
+}
+
+impl Default for TileOptions {
+    fn default() -> Self {
+        Self {
+            tile_width: 512,
+            tile_height: 512,
+        }
+    }
+}
+
+impl TileOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tile_size(mut self, tile_width: u32, tile_height: u32) -> Self {
+        self.tile_width = tile_width.max(1);
+        self.tile_height = tile_height.max(1);
+        self
+    }
+}
+
+/// Image read from top to bottom, a few rows at a time
+pub trait RowSource<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    fn get_dimensions(&self) -> (u32, u32);
+
+    fn get_channels(&self) -> u8;
+
+    /// Reads the next `rows` rows as interleaved samples
+    fn read_rows(&mut self, rows: u32) -> Result<Vec<T>, Box<dyn std::error::Error>>;
+}
+
+/// Image written from top to bottom, a band of rows at a time
+pub trait RowSink<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    /// Called once before the first rows, with the size of the whole output image
+    fn start(
+        &mut self,
+        width: u32,
+        height: u32,
+        channels: u8,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    fn write_rows(&mut self, rows: &Images<T>) -> Result<(), Box<dyn std::error::Error>>;
+
+    fn finish(&mut self) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Reads the rows of an image held in memory
+pub struct ImageRowSource<'a, T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    image: &'a Images<T>,
+    next_row: u32,
+}
+
+impl<'a, T> ImageRowSource<'a, T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    pub fn new(image: &'a Images<T>) -> Self {
+        Self { image, next_row: 0 }
+    }
+}
+
+impl<T> RowSource<T> for ImageRowSource<'_, T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    fn get_dimensions(&self) -> (u32, u32) {
+        (self.image.get_width(), self.image.get_height())
+    }
+
+    fn get_channels(&self) -> u8 {
+        self.image.get_channels()
+    }
+
+    fn read_rows(&mut self, rows: u32) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        let end_row = self.next_row + rows;
+        if end_row > self.image.get_height() {
+            return Err("Reading rows past the end of the image".into());
+        }
+        let row_length = self.image.get_width() as usize * self.image.get_channels() as usize;
+        let samples = self.image.get_samples()
+            [self.next_row as usize * row_length..end_row as usize * row_length]
+            .to_vec();
+        self.next_row = end_row;
+
+        Ok(samples)
+    }
+}
+
+/// Collects the written rows into an image in memory
+pub struct ImageRowSink<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    width: u32,
+    height: u32,
+    channels: u8,
+    samples: Vec<T>,
+}
+
+impl<T> ImageRowSink<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_image(self) -> Images<T> {
+        Images::from_samples(self.width, self.height, self.channels, self.samples)
+    }
+}
+
+impl<T> Default for ImageRowSink<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            channels: 4,
+            samples: Vec::new(),
+        }
+    }
+}
+
+impl<T> RowSink<T> for ImageRowSink<T>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq,
+{
+    fn start(
+        &mut self,
+        width: u32,
+        height: u32,
+        channels: u8,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.width = width;
+        self.height = height;
+        self.channels = channels;
+        self.samples = Vec::with_capacity(width as usize * height as usize * channels as usize);
+
+        Ok(())
+    }
+
+    fn write_rows(&mut self, rows: &Images<T>) -> Result<(), Box<dyn std::error::Error>> {
+        self.samples.extend_from_slice(rows.get_samples());
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+/// Runs the operations tile by tile, with the tiles of a band processed in parallel.
+/// Each tile is read with a halo of the summed `tile_halo` of the operations,
+/// so the result matches running the operations over the whole image:
+/// neighborhood operations drop a border of their halo, and the output shrinks accordingly.
+/// Fails for operations without a `tile_halo`, or that change the size in any other way.
+pub fn process_tiled<T>(
+    source: &mut dyn RowSource<T>,
+    sink: &mut dyn RowSink<T>,
+    operations: &[Box<dyn Operation<T>>],
+    tile_options: &TileOptions,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let mut halo: u32 = 0;
+    for operation in operations.iter() {
+        match operation.tile_halo() {
+            Some(operation_halo) => halo += operation_halo,
+            None => return Err("The operations can not run tile by tile".into()),
+        }
+    }
+
+    let (width, height) = source.get_dimensions();
+    let channels = source.get_channels();
+    if width <= 2 * halo || height <= 2 * halo {
+        return Err(format!(
+            "The image of {}x{} is too small for a halo of {} pixels",
+            width, height, halo
+        )
+        .into());
+    }
+    let output_width = width - 2 * halo;
+    let output_height = height - 2 * halo;
+    let row_length = width as usize * channels as usize;
+
+    // Input rows from `buffer_start` onwards, covering the current band and its halo
+    let mut buffer: Vec<T> = Vec::new();
+    let mut buffer_start: u32 = 0;
+    let mut started = false;
+
+    for band_start in (0..output_height).step_by(tile_options.tile_height as usize) {
+        let band_height = tile_options.tile_height.min(output_height - band_start);
+        let input_end = band_start + band_height + 2 * halo;
+
+        let dropped_rows = (band_start - buffer_start) as usize;
+        buffer.drain(..dropped_rows * row_length);
+        buffer_start = band_start;
+        let buffered_rows = (buffer.len() / row_length) as u32;
+        let missing_rows = input_end - buffer_start - buffered_rows;
+        buffer.extend(source.read_rows(missing_rows)?);
+
+        let tile_starts: Vec<u32> = (0..output_width)
+            .step_by(tile_options.tile_width as usize)
+            .collect();
+        let tiles = tile_starts
+            .into_par_iter()
+            .map(|tile_start| {
+                let tile_width = tile_options.tile_width.min(output_width - tile_start);
+                let input_width = tile_width + 2 * halo;
+                let input_height = band_height + 2 * halo;
+
+                let mut samples = Vec::with_capacity(
+                    input_width as usize * input_height as usize * channels as usize,
+                );
+                for row in buffer.chunks_exact(row_length) {
+                    let start = tile_start as usize * channels as usize;
+                    samples.extend_from_slice(
+                        &row[start..start + input_width as usize * channels as usize],
+                    );
+                }
+
+                let mut tile = Images::from_samples(input_width, input_height, channels, samples);
+                for operation in operations.iter() {
+                    tile = operation.apply(&tile);
+                }
+                if tile.get_width() != tile_width || tile.get_height() != band_height {
+                    return Err(format!(
+                        "A tile of {}x{} came out as {}x{}, the halo of the operations is off",
+                        tile_width,
+                        band_height,
+                        tile.get_width(),
+                        tile.get_height()
+                    ));
+                }
+
+                Ok(tile)
+            })
+            .collect::<Result<Vec<Images<T>>, String>>()?;
+
+        let output_channels = tiles[0].get_channels();
+        if !started {
+            sink.start(output_width, output_height, output_channels)?;
+            started = true;
+        }
+
+        let mut band: Vec<T> = Vec::with_capacity(
+            output_width as usize * band_height as usize * output_channels as usize,
+        );
+        for y_index in 0..band_height as usize {
+            for tile in tiles.iter() {
+                let tile_row_length = tile.get_width() as usize * output_channels as usize;
+                band.extend_from_slice(
+                    &tile.get_samples()[y_index * tile_row_length..(y_index + 1) * tile_row_length],
+                );
+            }
+        }
+        sink.write_rows(&Images::from_samples(
+            output_width,
+            band_height,
+            output_channels,
+            band,
+        ))?;
+    }
+    sink.finish()?;
+
+    Ok(())
+}
+
+/// Runs the operations tile by tile over an image held in memory
+pub fn apply_tiled<T>(
+    image: &Images<T>,
+    operations: &[Box<dyn Operation<T>>],
+    tile_options: &TileOptions,
+) -> Result<Images<T>, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let mut source = ImageRowSource::new(image);
+    let mut sink = ImageRowSink::new();
+    process_tiled(&mut source, &mut sink, operations, tile_options)?;
+
+    Ok(sink.into_image())
+}
@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use log::{debug, info, warn};
+use tokio::fs;
+
+use crate::utils::directory::list_images;
+
+use super::{
+    operations::{process_files, Operation, ProcessOptions},
+    sample::Sample,
+};
+
+/// How often `watch_folder` looks at the input folder, once a second by default,
+/// and how long a file has to stay the same before it is processed, two seconds by default.
+/// The settle time keeps files that are still being copied in from being read half written.
+#[derive(Debug, Clone, Copy)]
+pub struct WatchOptions {
+    poll_interval: Duration,
+    settle_time: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(1),
+            settle_time: Duration::from_secs(2),
+        }
+    }
+}
+
+impl WatchOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn with_settle_time(mut self, settle_time: Duration) -> Self {
+        self.settle_time = settle_time;
+        self
+    }
+
+    pub fn get_poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    pub fn get_settle_time(&self) -> Duration {
+        self.settle_time
+    }
+}
+
+/// Size and modification time of a file, which change while it is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileState {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileState {
+    pub fn new(len: u64, modified: Option<SystemTime>) -> Self {
+        Self { len, modified }
+    }
+}
+
+/// Keeps track of the files of a watched folder between polls
+#[derive(Debug, Clone, Default)]
+pub struct FolderWatcher {
+    settle_time: Duration,
+    /// State of the files when they were last handed out
+    handled: HashMap<PathBuf, FileState>,
+    /// New or modified files, with the state they were last seen in and since when
+    changing: HashMap<PathBuf, (FileState, Instant)>,
+}
+
+impl FolderWatcher {
+    pub fn new(settle_time: Duration) -> Self {
+        Self {
+            settle_time,
+            ..Self::default()
+        }
+    }
+
+    /// Takes the files found by a poll at `now`, and returns the new or modified files
+    /// that kept the same state for the settle time, in the order they were given.
+    /// Files missing from the poll are forgotten, so they are processed again when they come back.
+    pub fn poll(&mut self, files: &[(PathBuf, FileState)], now: Instant) -> Vec<PathBuf> {
+        let found: HashMap<&PathBuf, FileState> =
+            files.iter().map(|(path, state)| (path, *state)).collect();
+        self.handled.retain(|path, _| found.contains_key(path));
+        self.changing.retain(|path, _| found.contains_key(path));
+
+        let mut ready = Vec::new();
+        for (path, state) in files.iter() {
+            if self.handled.get(path) == Some(state) {
+                self.changing.remove(path);
+                continue;
+            }
+            match self.changing.get(path) {
+                Some((seen, since)) if seen == state => {
+                    if now.duration_since(*since) >= self.settle_time {
+                        self.changing.remove(path);
+                        self.handled.insert(path.clone(), *state);
+                        ready.push(path.clone());
+                    }
+                }
+                _ => {
+                    self.changing.insert(path.clone(), (*state, now));
+                }
+            }
+        }
+
+        ready
+    }
+}
+
+/// Watches `dir` and processes new or modified images into `dir_out` once they settle,
+/// until the cancellation token of the options is cancelled.
+/// Images already in the folder are processed on start, so pair it with an incremental mode
+/// to leave up to date outputs alone. A failing image is logged and the watch goes on,
+/// and it is tried again once it is modified.
+pub async fn watch_folder<T>(
+    dir: &str,
+    dir_out: &str,
+    operations: Arc<Vec<Box<dyn Operation<T>>>>,
+    print_stats: bool,
+    options: &ProcessOptions,
+    watch_options: &WatchOptions,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let options = options.clone().with_continue_on_error(true);
+    let mut watcher = FolderWatcher::new(watch_options.settle_time);
+    info!("Watching {:?} for images", dir);
+
+    while !options.get_cancellation().is_cancelled() {
+        let files = match poll_folder(dir, &options).await {
+            Ok(files) => files,
+            Err(error) => {
+                warn!("Failed to read {:?}: {}", dir, error);
+                Vec::new()
+            }
+        };
+        let ready = watcher.poll(&files, Instant::now());
+        if !ready.is_empty() {
+            debug!("Found {} new or modified image(s)", ready.len());
+            process_files(
+                dir,
+                &ready,
+                dir_out,
+                operations.clone(),
+                print_stats,
+                &options,
+            )
+            .await?;
+        }
+
+        tokio::time::sleep(watch_options.poll_interval).await;
+    }
+    info!("Stopped watching {:?}", dir);
+
+    Ok(())
+}
+
+/// Images of the folder selected by the options, with their current state
+async fn poll_folder(
+    dir: &str,
+    options: &ProcessOptions,
+) -> Result<Vec<(PathBuf, FileState)>, Box<dyn std::error::Error>> {
+    let (images, _) = list_images(dir, options.get_directory_options()).await?;
+    let mut files = Vec::with_capacity(images.len());
+    for relative_path in images {
+        // Files removed since the listing are left for the next poll
+        if let Ok(metadata) = fs::metadata(Path::new(dir).join(&relative_path)).await {
+            let state = FileState::new(metadata.len(), metadata.modified().ok());
+            files.push((relative_path, state));
+        }
+    }
+
+    Ok(files)
+}
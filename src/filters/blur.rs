@@ -1,8 +1,16 @@
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::core::{image::Images, operations::Operation, pixel::Pixels};
+use crate::{
+    core::{
+        image::Images,
+        operations::Operation,
+        pixel::Pixels,
+        sample::{RoundingMode, Sample},
+    },
+    utils::color_space_converter::{linear_to_sample, sample_to_linear},
+};
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub enum SmoothingKernelChoices {
     Gaussian,
     BoxBlur,
@@ -15,20 +23,40 @@ fn select_smoothing_kernel(choice: SmoothingKernelChoices) -> Vec<u8> {
     }
 }
 
+#[derive(Debug)]
 pub struct Blur {
     kernel_choice: SmoothingKernelChoices,
+    linear_light: bool,
+    rounding: RoundingMode,
 }
 
 impl Blur {
     pub fn new(kernel_choice: SmoothingKernelChoices) -> Self {
-        Self { kernel_choice }
+        Self {
+            kernel_choice,
+            linear_light: false,
+            rounding: RoundingMode::Truncate,
+        }
+    }
+
+    /// Rounding of the normalized kernel sum for integer samples, truncating by default
+    pub fn with_rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Averages in linear light instead of on the gamma encoded sRGB values,
+    /// which keeps edges between bright and dark areas from darkening
+    pub fn with_linear_light(mut self, linear_light: bool) -> Self {
+        self.linear_light = linear_light;
+        self
     }
 }
 
 // AI: Algorithm from Gemini
 impl<T> Operation<T> for Blur
 where
-    T: Copy + Clone + From<u8> + Into<u32> + std::cmp::PartialEq + Send + Sync, // Send + Sync required for Rayon to safely pass T between threads
+    T: Sample, // Sample includes Send + Sync, required for Rayon to safely pass T between threads
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
         let kernel = select_smoothing_kernel(self.kernel_choice);
@@ -36,6 +64,22 @@ where
         let kernel_normalizer: u32 = kernel.iter().map(|x| *x as u32).sum(); // Kernel sum normalization gives darker images
                                                                              // let kernel_normalizer: u32 = *kernel.iter().max().unwrap() as u32;
         let half_kernel_size = kernel_size / 2;
+        let linear_light = self.linear_light;
+        let rounding = self.rounding;
+        let decode = |value: T| -> f64 {
+            if linear_light {
+                sample_to_linear(value)
+            } else {
+                value.to_f64()
+            }
+        };
+        let encode = |sum: f64| -> T {
+            if linear_light {
+                linear_to_sample(sum / kernel_normalizer as f64)
+            } else {
+                T::from_f64_rounded(sum / kernel_normalizer as f64, rounding)
+            }
+        };
 
         let output_width = old_image.get_width() - 2 * half_kernel_size;
         let output_height = old_image.get_height() - 2 * half_kernel_size;
@@ -46,7 +90,7 @@ where
                 (half_kernel_size..old_image.get_width() - half_kernel_size)
                     .into_par_iter()
                     .map(|x_index| {
-                        let sum_vec: Vec<(u32, u32, u32)> = (0..=half_kernel_size)
+                        let sum_vec: Vec<(f64, f64, f64)> = (0..=half_kernel_size)
                             .into_par_iter()
                             .flat_map(|dy| {
                                 (0..=half_kernel_size)
@@ -61,31 +105,26 @@ where
                                         let kernel_val =
                                             *kernel.get((dy * kernel_size + dx) as usize).unwrap();
                                         (
-                                            pixel.get_red().into() * (kernel_val as u32),
-                                            pixel.get_green().into() * (kernel_val as u32),
-                                            pixel.get_blue().into() * (kernel_val as u32),
+                                            decode(pixel.get_red()) * (kernel_val as f64),
+                                            decode(pixel.get_green()) * (kernel_val as f64),
+                                            decode(pixel.get_blue()) * (kernel_val as f64),
                                         )
                                     })
-                                    .collect::<Vec<(u32, u32, u32)>>()
+                                    .collect::<Vec<(f64, f64, f64)>>()
                             })
-                            .collect::<Vec<(u32, u32, u32)>>();
+                            .collect::<Vec<(f64, f64, f64)>>();
 
                         // Intermediate calculations using sum_r, sum_g, and sum_b to provide more accurate results
-                        let mut sum_r = 0;
-                        let mut sum_g = 0;
-                        let mut sum_b = 0;
+                        let mut sum_r = 0.0;
+                        let mut sum_g = 0.0;
+                        let mut sum_b = 0.0;
                         for sum in sum_vec.iter() {
                             sum_r += sum.0;
                             sum_g += sum.1;
                             sum_b += sum.2;
                         }
 
-                        Pixels::<T>::new(
-                            ((sum_r / kernel_normalizer).clamp(0, 255) as u8).into(),
-                            ((sum_g / kernel_normalizer).clamp(0, 255) as u8).into(),
-                            ((sum_b / kernel_normalizer).clamp(0, 255) as u8).into(),
-                            255_u8.into(),
-                        )
+                        Pixels::<T>::new(encode(sum_r), encode(sum_g), encode(sum_b), T::MAX_VALUE)
                     })
                     .collect::<Vec<Pixels<T>>>()
             })
@@ -98,4 +137,15 @@ where
             new_pixel.clone(),
         )
     }
+
+    fn tile_halo(&self) -> Option<u32> {
+        Some(1)
+    }
+
+    fn fingerprint(&self) -> String {
+        format!(
+            "blur:{:?},{},{:?}",
+            self.kernel_choice, self.linear_light, self.rounding
+        )
+    }
 }
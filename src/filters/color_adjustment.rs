@@ -0,0 +1,130 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    core::{image::Images, operations::Operation, pixel::Pixels, sample::Sample},
+    utils::color_space_converter::{color_to_pixel, pixel_to_color, ColorSpace, Hsl, Hsv},
+};
+
+/// Converts every pixel into color space `C`, adjusts it and converts it back, keeping alpha
+fn adjust_pixels<C, T, F>(old_image: &Images<T>, adjust: F) -> Images<T>
+where
+    C: ColorSpace,
+    T: Sample,
+    F: Fn(C) -> C + Send + Sync,
+{
+    let new_pixel = old_image
+        .get_image()
+        .into_par_iter()
+        .map(|pix| {
+            let color: C = pixel_to_color(&pix);
+            color_to_pixel(&adjust(color), pix.get_alpha())
+        })
+        .collect::<Vec<Pixels<T>>>();
+
+    Images::new(
+        old_image.get_width(),
+        old_image.get_height(),
+        old_image.get_channels(),
+        new_pixel,
+    )
+}
+
+/// Rotates the hue of every pixel by the given number of degrees (HSV)
+#[derive(Debug)]
+pub struct HueShift {
+    degrees: f64,
+}
+
+impl HueShift {
+    pub fn new(degrees: f64) -> Self {
+        Self { degrees }
+    }
+}
+
+impl<T> Operation<T> for HueShift
+where
+    T: Sample,
+{
+    fn apply(&self, old_image: &Images<T>) -> Images<T> {
+        adjust_pixels(old_image, |hsv: Hsv| Hsv {
+            hue: (hsv.hue + self.degrees).rem_euclid(360.0),
+            ..hsv
+        })
+    }
+
+    fn tile_halo(&self) -> Option<u32> {
+        Some(0)
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("hue:{}", self.degrees)
+    }
+}
+
+/// Scales the saturation of every pixel (HSL): 0.0 gives grayscale, 1.0 keeps the image
+#[derive(Debug)]
+pub struct Saturation {
+    factor: f64,
+}
+
+impl Saturation {
+    pub fn new(factor: f64) -> Self {
+        Self { factor }
+    }
+}
+
+impl<T> Operation<T> for Saturation
+where
+    T: Sample,
+{
+    fn apply(&self, old_image: &Images<T>) -> Images<T> {
+        adjust_pixels(old_image, |hsl: Hsl| Hsl {
+            saturation: (hsl.saturation * self.factor).clamp(0.0, 1.0),
+            ..hsl
+        })
+    }
+
+    fn tile_halo(&self) -> Option<u32> {
+        Some(0)
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("saturation:{}", self.factor)
+    }
+}
+
+/// Saturation boost weighted towards muted colors (HSV): already saturated pixels and
+/// grays are barely changed. Negative amounts mute the image instead.
+#[derive(Debug)]
+pub struct Vibrance {
+    amount: f64,
+}
+
+impl Vibrance {
+    pub fn new(amount: f64) -> Self {
+        Self { amount }
+    }
+}
+
+impl<T> Operation<T> for Vibrance
+where
+    T: Sample,
+{
+    fn apply(&self, old_image: &Images<T>) -> Images<T> {
+        adjust_pixels(old_image, |hsv: Hsv| {
+            let boost = self.amount * (1.0 - hsv.saturation);
+            Hsv {
+                saturation: (hsv.saturation * (1.0 + boost)).clamp(0.0, 1.0),
+                ..hsv
+            }
+        })
+    }
+
+    fn tile_halo(&self) -> Option<u32> {
+        Some(0)
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("vibrance:{}", self.amount)
+    }
+}
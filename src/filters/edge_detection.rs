@@ -1,8 +1,13 @@
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::core::{image::Images, operations::Operation, pixel::Pixels};
+use crate::core::{
+    image::Images,
+    operations::Operation,
+    pixel::Pixels,
+    sample::{RoundingMode, Sample},
+};
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub enum EdgeDetectingKernelChoices {
     Outline,
     SobelX,
@@ -19,25 +24,37 @@ fn select_edge_detecting_kernel(choice: EdgeDetectingKernelChoices) -> Vec<i32>
     }
 }
 
+#[derive(Debug)]
 pub struct EdgeDetection {
     kernel_choice: EdgeDetectingKernelChoices,
+    rounding: RoundingMode,
 }
 
 impl EdgeDetection {
     pub fn new(kernel_choice: EdgeDetectingKernelChoices) -> Self {
-        Self { kernel_choice }
+        Self {
+            kernel_choice,
+            rounding: RoundingMode::Truncate,
+        }
+    }
+
+    /// Rounding of the kernel sum for integer samples, truncating by default
+    pub fn with_rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
     }
 }
 
 impl<T> Operation<T> for EdgeDetection
 where
-    T: Copy + Clone + From<u8> + Into<u32> + std::cmp::PartialEq + Send + Sync,
+    T: Sample,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
         let kernel: Vec<i32> = select_edge_detecting_kernel(self.kernel_choice);
 
         let kernel_size: u32 = 3;
         let half_kernel_size = kernel_size / 2;
+        let rounding = self.rounding;
 
         let output_width = old_image.get_width() - 2 * half_kernel_size;
         let output_height = old_image.get_height() - 2 * half_kernel_size;
@@ -48,7 +65,7 @@ where
                 (half_kernel_size..old_image.get_width() - half_kernel_size)
                     .into_par_iter()
                     .map(|x_index| {
-                        let sum_vec: Vec<(i32, i32, i32)> = (0..kernel_size)
+                        let sum_vec: Vec<(f64, f64, f64)> = (0..kernel_size)
                             .into_par_iter()
                             .flat_map(|dy| {
                                 (0..kernel_size)
@@ -63,19 +80,19 @@ where
                                         let kernel_val =
                                             *kernel.get((dy * kernel_size + dx) as usize).unwrap();
                                         (
-                                            (pixel.get_red().into() as i32) * (kernel_val),
-                                            (pixel.get_green().into() as i32) * (kernel_val),
-                                            (pixel.get_blue().into() as i32) * (kernel_val),
+                                            pixel.get_red().to_f64() * kernel_val as f64,
+                                            pixel.get_green().to_f64() * kernel_val as f64,
+                                            pixel.get_blue().to_f64() * kernel_val as f64,
                                         )
                                     })
-                                    .collect::<Vec<(i32, i32, i32)>>()
+                                    .collect::<Vec<(f64, f64, f64)>>()
                             })
-                            .collect::<Vec<(i32, i32, i32)>>();
+                            .collect::<Vec<(f64, f64, f64)>>();
 
                         // Intermediate calculations using sum_r, sum_g, and sum_b to provide more accurate results
-                        let mut sum_r = 0;
-                        let mut sum_g = 0;
-                        let mut sum_b = 0;
+                        let mut sum_r = 0.0;
+                        let mut sum_g = 0.0;
+                        let mut sum_b = 0.0;
                         for sum in sum_vec.iter() {
                             sum_r += sum.0;
                             sum_g += sum.1;
@@ -87,10 +104,10 @@ where
                         // which means normalizing by the sum would lead to incorrect results.
 
                         Pixels::new(
-                            (sum_r.clamp(0, 255) as u8).into(),
-                            (sum_g.clamp(0, 255) as u8).into(),
-                            (sum_b.clamp(0, 255) as u8).into(),
-                            255_u8.into(),
+                            T::from_f64_rounded(sum_r, rounding),
+                            T::from_f64_rounded(sum_g, rounding),
+                            T::from_f64_rounded(sum_b, rounding),
+                            T::MAX_VALUE,
                         )
                     })
                     .collect::<Vec<Pixels<T>>>()
@@ -104,4 +121,15 @@ where
             new_pixel.clone(),
         )
     }
+
+    fn tile_halo(&self) -> Option<u32> {
+        Some(1)
+    }
+
+    fn fingerprint(&self) -> String {
+        format!(
+            "edge_detection:{:?},{:?}",
+            self.kernel_choice, self.rounding
+        )
+    }
 }
@@ -1,5 +1,6 @@
-use crate::core::{image::Images, operations::Operation, pixel::Pixels};
+use crate::core::{image::Images, operations::Operation, pixel::Pixels, sample::Sample};
 
+#[derive(Debug)]
 pub struct GammaCorrection {
     gamma: f64,
 }
@@ -12,17 +13,20 @@ impl GammaCorrection {
 
 impl<T> Operation<T> for GammaCorrection
 where
-    T: Copy + Clone + From<u8> + Into<f64> + std::cmp::PartialEq + Send + Sync,
+    T: Sample,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
         let new_image = old_image
             .get_image()
             .iter()
             .map(|pix| {
-                let r = (((pix.get_red().into() / 255.0).powf(self.gamma) * 255.0) as u8).into();
-                let g = (((pix.get_green().into() / 255.0).powf(self.gamma) * 255.0) as u8).into();
-                let b = (((pix.get_blue().into() / 255.0).powf(self.gamma) * 255.0) as u8).into();
-                let a = (((pix.get_alpha().into() / 255.0).powf(self.gamma) * 255.0) as u8).into();
+                let correct = |value: T| -> T {
+                    T::from_f64(value.to_unit().powf(self.gamma) * T::MAX_VALUE.to_f64())
+                };
+                let r = correct(pix.get_red());
+                let g = correct(pix.get_green());
+                let b = correct(pix.get_blue());
+                let a = correct(pix.get_alpha());
 
                 Pixels::new(r, g, b, a)
             })
@@ -35,4 +39,12 @@ where
             new_image.clone(),
         )
     }
+
+    fn tile_halo(&self) -> Option<u32> {
+        Some(0)
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("gamma:{}", self.gamma)
+    }
 }
@@ -1,8 +1,8 @@
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::core::{image::Images, operations::Operation, pixel::Pixels};
+use crate::core::{image::Images, operations::Operation, pixel::Pixels, sample::Sample};
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub enum GrayScaleAlgorithms {
     Average,
     Luminosity,
@@ -10,26 +10,26 @@ pub enum GrayScaleAlgorithms {
 
 fn select_grayscale_algorithm<T>(algo: &GrayScaleAlgorithms, pix: &Pixels<T>) -> T
 where
-    T: Copy + Clone + From<u8> + Into<u32> + std::cmp::PartialEq,
+    T: Sample,
 {
     match algo {
-        GrayScaleAlgorithms::Average => (((pix.get_red().into() as f64
-            + pix.get_green().into() as f64
-            + pix.get_blue().into() as f64)
-            / 3.0) as u8)
-            .into(),
+        GrayScaleAlgorithms::Average => T::from_f64(
+            (pix.get_red().to_f64() + pix.get_green().to_f64() + pix.get_blue().to_f64()) / 3.0,
+        ),
 
         GrayScaleAlgorithms::Luminosity => {
             // Luminosity method: https://www.mathworks.com/help/matlab/ref/rgb2gray.html
-            ((((pix.get_red().into() as f64 * 0.299)
-                + (pix.get_green().into() as f64 * 0.5879)
-                + (pix.get_blue().into() as f64 * 0.114))
-                / 3.0) as u8)
-                .into()
+            T::from_f64(
+                ((pix.get_red().to_f64() * 0.299)
+                    + (pix.get_green().to_f64() * 0.5879)
+                    + (pix.get_blue().to_f64() * 0.114))
+                    / 3.0,
+            )
         }
     }
 }
 
+#[derive(Debug)]
 pub struct GrayScale {
     algo: GrayScaleAlgorithms,
 }
@@ -42,28 +42,37 @@ impl GrayScale {
 
 impl<T> Operation<T> for GrayScale
 where
-    T: Copy + Clone + From<u8> + Into<u32> + std::cmp::PartialEq + Send + Sync,
+    T: Sample,
 {
+    /// Produces a native gray image: 1 channel, or 2 when the input carries alpha
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
-        let new_pixel = old_image
+        let has_alpha = old_image.has_alpha();
+        let new_samples = old_image
             .get_image()
             .into_par_iter()
-            .map(|pix| {
+            .flat_map_iter(|pix| {
                 let grayscale_pixel = select_grayscale_algorithm(&self.algo, &pix);
-                Pixels::new(
-                    grayscale_pixel,
-                    grayscale_pixel,
-                    grayscale_pixel,
-                    pix.get_alpha(),
-                )
+                if has_alpha {
+                    vec![grayscale_pixel, pix.get_alpha()]
+                } else {
+                    vec![grayscale_pixel]
+                }
             })
-            .collect::<Vec<Pixels<T>>>();
+            .collect::<Vec<T>>();
 
-        Images::new(
+        Images::from_samples(
             old_image.get_width(),
             old_image.get_height(),
-            old_image.get_channels(),
-            new_pixel.clone(),
+            if has_alpha { 2 } else { 1 },
+            new_samples,
         )
     }
+
+    fn tile_halo(&self) -> Option<u32> {
+        Some(0)
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("grayscale:{:?}", self.algo)
+    }
 }
@@ -1,6 +1,6 @@
-use crate::core::{image::Images, operations::Operation, pixel::Pixels};
+use crate::core::{image::Images, operations::Operation, pixel::Pixels, sample::Sample};
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub enum MorphologicalKernelChoices {
     Cross,
     Diamond,
@@ -33,6 +33,24 @@ fn choose_kernel(kernel: MorphologicalKernelChoices) -> Vec<i32> {
     }
 }
 
+// Samples are only PartialOrd (f32), so Ord::min and Ord::max are not available
+fn partial_min<T: PartialOrd>(lhs: T, rhs: T) -> T {
+    if rhs < lhs {
+        rhs
+    } else {
+        lhs
+    }
+}
+
+fn partial_max<T: PartialOrd>(lhs: T, rhs: T) -> T {
+    if rhs > lhs {
+        rhs
+    } else {
+        lhs
+    }
+}
+
+#[derive(Debug)]
 pub struct Erosion {
     kernel_choice: MorphologicalKernelChoices,
 }
@@ -45,7 +63,7 @@ impl Erosion {
 
 impl<T> Operation<T> for Erosion
 where
-    T: Copy + Clone + From<u8> + From<u8> + Into<u32> + std::cmp::PartialEq + Ord + Send + Sync,
+    T: Sample,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
         let mut new_image: Images<T> = Images::new(
@@ -65,7 +83,8 @@ where
 
         for y_index in 1..old_image.get_height() - 1 {
             for x_index in 1..old_image.get_width() - 1 {
-                let mut min_val: (T, T, T, T) = (255.into(), 255.into(), 255.into(), 255.into());
+                let mut min_val: (T, T, T, T) =
+                    (T::MAX_VALUE, T::MAX_VALUE, T::MAX_VALUE, T::MAX_VALUE);
 
                 for index in 0..kernel.len() {
                     let dx = (index % 3) as i32 - 1;
@@ -82,10 +101,10 @@ where
                             )
                             .unwrap();
                         if *kernel.get(index).unwrap() != 0 {
-                            min_val.0 = min_val.0.min(pix.get_red());
-                            min_val.1 = min_val.1.min(pix.get_green());
-                            min_val.2 = min_val.2.min(pix.get_blue());
-                            min_val.3 = min_val.3.min(pix.get_alpha());
+                            min_val.0 = partial_min(min_val.0, pix.get_red());
+                            min_val.1 = partial_min(min_val.1, pix.get_green());
+                            min_val.2 = partial_min(min_val.2, pix.get_blue());
+                            min_val.3 = partial_min(min_val.3, pix.get_alpha());
                         }
                     }
                 }
@@ -101,8 +120,21 @@ where
 
         new_image
     }
+
+    fn fingerprint(&self) -> String {
+        format!("erosion:{:?}", self.kernel_choice)
+    }
+
+    fn check_input_size(&self, input_size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
+        check_kernel_fits(input_size)
+    }
+
+    fn output_size(&self, input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some(input_size)
+    }
 }
 
+#[derive(Debug)]
 pub struct Dilation {
     kernel_choice: MorphologicalKernelChoices,
 }
@@ -115,7 +147,7 @@ impl Dilation {
 
 impl<T> Operation<T> for Dilation
 where
-    T: Copy + Clone + From<u8> + From<u8> + Into<u32> + std::cmp::PartialEq + Ord + Send + Sync,
+    T: Sample,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
         let mut new_image: Images<T> = Images::new(
@@ -135,7 +167,8 @@ where
 
         for y_index in 1..old_image.get_height() - 1 {
             for x_index in 1..old_image.get_width() - 1 {
-                let mut max_val: (T, T, T, T) = (0.into(), 0.into(), 0.into(), 0.into());
+                let mut max_val: (T, T, T, T) =
+                    (T::MIN_VALUE, T::MIN_VALUE, T::MIN_VALUE, T::MIN_VALUE);
 
                 for index in 0..kernel.len() {
                     let dx = (index % 3) as i32 - 1;
@@ -152,10 +185,10 @@ where
                             )
                             .unwrap();
                         if *kernel.get(index).unwrap() != 0 {
-                            max_val.0 = max_val.0.max(pix.get_red());
-                            max_val.1 = max_val.1.max(pix.get_green());
-                            max_val.2 = max_val.2.max(pix.get_blue());
-                            max_val.3 = max_val.3.max(pix.get_alpha());
+                            max_val.0 = partial_max(max_val.0, pix.get_red());
+                            max_val.1 = partial_max(max_val.1, pix.get_green());
+                            max_val.2 = partial_max(max_val.2, pix.get_blue());
+                            max_val.3 = partial_max(max_val.3, pix.get_alpha());
                         }
                     }
                 }
@@ -171,4 +204,29 @@ where
 
         new_image
     }
+
+    fn fingerprint(&self) -> String {
+        format!("dilation:{:?}", self.kernel_choice)
+    }
+
+    fn check_input_size(&self, input_size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
+        check_kernel_fits(input_size)
+    }
+
+    fn output_size(&self, input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some(input_size)
+    }
+}
+
+/// The 3x3 kernels need at least one pixel with a full neighborhood
+fn check_kernel_fits(input_size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
+    if input_size.0 < 3 || input_size.1 < 3 {
+        return Err(format!(
+            "An image of {}x{} is too small for a 3x3 kernel",
+            input_size.0, input_size.1
+        )
+        .into());
+    }
+
+    Ok(())
 }
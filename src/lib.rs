@@ -1,13 +1,23 @@
 // Inline Module Declarations
 
 pub mod core {
+    pub mod animation;
+    pub mod cache;
     pub mod image;
+    pub mod mask;
     pub mod operations;
+    pub mod pipeline;
     pub mod pixel;
+    pub mod progress;
+    pub mod report;
+    pub mod sample;
+    pub mod tiling;
+    pub mod watch;
 }
 
 pub mod filters {
     pub mod blur;
+    pub mod color_adjustment;
     pub mod edge_detection;
     pub mod gamma_correction;
     pub mod gray_scale;
@@ -22,7 +32,12 @@ pub mod transformations {
 }
 
 pub mod utils {
+    pub mod chart;
     pub mod color_space_converter;
+    pub mod directory;
     pub mod image_io;
+    pub mod json;
+    pub mod logging;
+    pub mod metadata;
     pub mod statistics;
 }
@@ -1,5 +1,11 @@
+use std::sync::Arc;
+
 use image_processor::{
-    core::operations::{process_images, Operation},
+    core::{
+        cache::IncrementalMode,
+        operations::{process_images, Operation, ProcessOptions},
+        watch::{watch_folder, WatchOptions},
+    },
     filters::{
         blur::{Blur, SmoothingKernelChoices},
         edge_detection::{EdgeDetectingKernelChoices, EdgeDetection},
@@ -13,6 +19,10 @@ use image_processor::{
         resize::ResizeBilinearInterpolation,
         rotate::{Flip90Right, FlipHorizontal},
     },
+    utils::{
+        image_io::{OutputOptions, ReadOptions},
+        logging::init_stdout_logger,
+    },
 };
 
 const IS_DIR: bool = false;
@@ -20,9 +30,13 @@ const DIR: &str = "assets/";
 const DIR_OUT: &str = "assets_out/";
 const PATH: &str = "assets/lenna.png";
 const PRINT_STATS: bool = true;
+/// Keeps processing new or modified images in DIR until stopped with Ctrl+C
+const WATCH: bool = false;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    init_stdout_logger()?;
+
     let operations: Vec<Box<dyn Operation<u8>>> = vec![
         Box::new(FlipHorizontal::new()),
         Box::new(Flip90Right::new()),
@@ -36,13 +50,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Box::new(GammaCorrection::new(1.5)),
     ];
 
+    let options = ProcessOptions::new()
+        .with_read_options(ReadOptions::new().with_auto_orient(true))
+        .with_output_options(OutputOptions::new().with_keep_metadata(true));
+
+    if WATCH {
+        let options = options.with_incremental(IncrementalMode::ContentHash);
+        let cancellation = options.get_cancellation().clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                cancellation.cancel();
+            }
+        });
+        watch_folder(
+            DIR,
+            DIR_OUT,
+            Arc::new(operations),
+            PRINT_STATS,
+            &options,
+            &WatchOptions::new(),
+        )
+        .await?;
+
+        return Ok(());
+    }
+
     process_images(
         IS_DIR,
         Some(DIR),
         DIR_OUT,
         Some(PATH),
-        &operations,
+        Arc::new(operations),
         PRINT_STATS,
+        &options,
     )
     .await?;
 
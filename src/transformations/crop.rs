@@ -1,7 +1,8 @@
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::core::{image::Images, operations::Operation, pixel::Pixels};
+use crate::core::{image::Images, operations::Operation};
 
+#[derive(Debug)]
 pub struct Crop {
     top_left_point: (u32, u32),
     new_width: u32,
@@ -23,28 +24,62 @@ where
     T: Copy + Clone + From<u8> + std::cmp::PartialEq + Send + Sync,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
-        let new_pixel = (0..self.new_height as usize)
+        let new_samples = (0..self.new_height as usize)
             .into_par_iter()
             .flat_map(|y_index| {
                 (0..self.new_width as usize)
                     .into_par_iter()
-                    .map(|x_index| {
+                    .flat_map_iter(|x_index| {
                         old_image
-                            .get_pixel_at(
+                            .get_pixel_samples(
                                 self.top_left_point.0 + x_index as u32,
                                 self.top_left_point.1 + y_index as u32,
                             )
                             .unwrap()
+                            .to_vec()
                     })
-                    .collect::<Vec<Pixels<T>>>()
+                    .collect::<Vec<T>>()
             })
-            .collect::<Vec<Pixels<T>>>();
+            .collect::<Vec<T>>();
 
-        Images::new(
+        Images::from_samples(
             self.new_width,
             self.new_height,
             old_image.get_channels(),
-            new_pixel.clone(),
+            new_samples,
         )
     }
+
+    fn output_offset(
+        &self,
+        _input_size: (u32, u32),
+        _output_size: (u32, u32),
+    ) -> Option<(u32, u32)> {
+        Some(self.top_left_point)
+    }
+
+    fn fingerprint(&self) -> String {
+        format!(
+            "crop:{},{},{},{}",
+            self.top_left_point.0, self.top_left_point.1, self.new_width, self.new_height
+        )
+    }
+
+    fn output_size(&self, _input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some((self.new_width, self.new_height))
+    }
+
+    fn check_input_size(&self, input_size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
+        let right = self.top_left_point.0 as u64 + self.new_width as u64;
+        let bottom = self.top_left_point.1 as u64 + self.new_height as u64;
+        if right > input_size.0 as u64 || bottom > input_size.1 as u64 {
+            return Err(format!(
+                "Crop of {}x{} at {:?} is outside of the {}x{} image",
+                self.new_width, self.new_height, self.top_left_point, input_size.0, input_size.1
+            )
+            .into());
+        }
+
+        Ok(())
+    }
 }
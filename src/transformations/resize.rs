@@ -1,7 +1,11 @@
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::core::{image::Images, operations::Operation, pixel::Pixels};
+use crate::{
+    core::{image::Images, operations::Operation, pixel::Pixels, sample::Sample},
+    utils::color_space_converter::{linear_to_srgb, srgb_to_linear},
+};
 
+#[derive(Debug)]
 pub struct ResizeNearestNeighbour {
     new_width: u32,
     new_height: u32,
@@ -21,44 +25,57 @@ where
     T: Copy + Clone + From<u8> + std::cmp::PartialEq + Send + Sync,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
-        let mut new_image = Images::new(
-            self.new_width,
-            self.new_height,
-            old_image.get_channels(),
-            Vec::new(),
-        );
-
         let x_ratio = old_image.get_width() as f64 / self.new_width as f64;
         let y_ratio = old_image.get_height() as f64 / self.new_height as f64;
 
-        let pixel_list = (0..self.new_height)
+        let sample_list = (0..self.new_height)
             .into_par_iter()
             .flat_map(|y_index| {
                 (0..self.new_width)
                     .into_par_iter()
-                    .map(move |x_index| {
+                    .flat_map_iter(move |x_index| {
                         old_image
-                            .get_pixel_at(
+                            .get_pixel_samples(
                                 (x_index as f64 * x_ratio) as u32,
                                 (y_index as f64 * y_ratio) as u32,
                             )
                             .unwrap()
+                            .to_vec()
                     })
-                    .collect::<Vec<Pixels<T>>>()
+                    .collect::<Vec<T>>()
             })
-            .collect::<Vec<Pixels<T>>>();
+            .collect::<Vec<T>>();
 
-        for pix in pixel_list.iter() {
-            new_image.add_pixel(pix.clone());
-        }
+        Images::from_samples(
+            self.new_width,
+            self.new_height,
+            old_image.get_channels(),
+            sample_list,
+        )
+    }
 
-        new_image
+    fn output_offset(
+        &self,
+        _input_size: (u32, u32),
+        _output_size: (u32, u32),
+    ) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("resize:{},{}", self.new_width, self.new_height)
+    }
+
+    fn output_size(&self, _input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some((self.new_width, self.new_height))
     }
 }
 
+#[derive(Debug)]
 pub struct ResizeBilinearInterpolation {
     new_width: u32,
     new_height: u32,
+    linear_light: bool,
 }
 
 impl ResizeBilinearInterpolation {
@@ -66,13 +83,40 @@ impl ResizeBilinearInterpolation {
         Self {
             new_width,
             new_height,
+            linear_light: false,
         }
     }
+
+    /// Interpolates in linear light instead of on the gamma encoded sRGB values,
+    /// so that downscaled images keep their brightness
+    pub fn with_linear_light(mut self, linear_light: bool) -> Self {
+        self.linear_light = linear_light;
+        self
+    }
+}
+
+/// Bilinear interpolation of one channel in 0.0..=1.0, optionally carried out in linear light.
+/// Alpha is never gamma encoded, so it is always interpolated directly.
+fn interpolate_channel(corners: [f64; 4], x_diff: f64, y_diff: f64, linearize: bool) -> f64 {
+    let [top_left, top_right, bottom_left, bottom_right] = if linearize {
+        corners.map(srgb_to_linear)
+    } else {
+        corners
+    };
+    let top = top_left + (top_right - top_left) * x_diff;
+    let bottom = bottom_left + (bottom_right - bottom_left) * x_diff;
+    let value = top + (bottom - top) * y_diff;
+
+    if linearize {
+        linear_to_srgb(value.clamp(0.0, 1.0))
+    } else {
+        value
+    }
 }
 
 impl<T> Operation<T> for ResizeBilinearInterpolation
 where
-    T: Copy + Clone + From<u8> + Into<f64> + std::cmp::PartialEq + Send + Sync,
+    T: Sample,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
         let x_ratio = old_image.get_width() as f64 / self.new_width as f64;
@@ -98,6 +142,26 @@ where
                         let bottom_left: Pixels<T> = old_image.get_pixel_at(x1, y2).unwrap();
                         let bottom_right: Pixels<T> = old_image.get_pixel_at(x2, y2).unwrap();
 
+                        if self.linear_light {
+                            let channel = |get: fn(&Pixels<T>) -> T, linearize: bool| -> T {
+                                let corners = [
+                                    get(&top_left).to_unit(),
+                                    get(&top_right).to_unit(),
+                                    get(&bottom_left).to_unit(),
+                                    get(&bottom_right).to_unit(),
+                                ];
+                                T::from_unit(interpolate_channel(
+                                    corners, x_diff, y_diff, linearize,
+                                ))
+                            };
+                            return Pixels::new(
+                                channel(Pixels::get_red, true),
+                                channel(Pixels::get_green, true),
+                                channel(Pixels::get_blue, true),
+                                channel(Pixels::get_alpha, false),
+                            );
+                        }
+
                         let top: Pixels<T> = top_left.clone() + (top_right - top_left) * x_diff;
                         let bottom: Pixels<T> =
                             bottom_left.clone() + (bottom_right - bottom_left) * x_diff;
@@ -115,4 +179,23 @@ where
             new_pixel.clone(),
         )
     }
+
+    fn output_offset(
+        &self,
+        _input_size: (u32, u32),
+        _output_size: (u32, u32),
+    ) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn fingerprint(&self) -> String {
+        format!(
+            "resize_bilinear:{},{},{}",
+            self.new_width, self.new_height, self.linear_light
+        )
+    }
+
+    fn output_size(&self, _input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some((self.new_width, self.new_height))
+    }
 }
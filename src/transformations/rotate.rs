@@ -1,7 +1,9 @@
 use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
 
-use crate::core::{image::Images, operations::Operation, pixel::Pixels};
+use crate::core::{image::Images, operations::Operation};
 
+/// Reverses both the rows and the columns, a rotation by 180 degrees
+#[derive(Debug)]
 pub struct FlipVertical {}
 
 impl Default for FlipVertical {
@@ -21,13 +23,16 @@ where
     T: Copy + Clone + From<u8> + std::cmp::PartialEq + Send + Sync,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
-        let original_image = old_image.get_image().clone();
+        let original_image: Vec<&[T]> = old_image
+            .get_samples()
+            .chunks_exact(old_image.get_channels() as usize)
+            .collect();
 
-        let new_pixel: Vec<Pixels<T>> = (0..old_image.get_height())
+        let new_pixel: Vec<&[T]> = (0..old_image.get_height())
             .into_par_iter()
             .rev()
             .flat_map(|y_index| {
-                let mut vec_slice: Vec<Pixels<T>> = Vec::from_iter(
+                let mut vec_slice: Vec<&[T]> = Vec::from_iter(
                     original_image[(y_index * old_image.get_width()) as usize
                         ..((y_index * old_image.get_width()) + old_image.get_width()) as usize]
                         .iter()
@@ -37,19 +42,29 @@ where
                 vec_slice.reverse();
                 vec_slice
             })
-            .collect::<Vec<Pixels<T>>>();
+            .collect::<Vec<&[T]>>();
 
-        let flipped_image: Images<T> = Images::new(
+        let flipped_image: Images<T> = Images::from_samples(
             old_image.get_width(),
             old_image.get_height(),
             old_image.get_channels(),
-            new_pixel.clone(),
+            new_pixel.concat(),
         );
 
         flipped_image
     }
+
+    fn fingerprint(&self) -> String {
+        "flip_vertical".to_string()
+    }
+
+    fn output_size(&self, input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some(input_size)
+    }
 }
 
+/// Mirrors the image left to right
+#[derive(Debug)]
 pub struct FlipHorizontal {}
 
 impl Default for FlipHorizontal {
@@ -69,12 +84,15 @@ where
     T: Copy + Clone + From<u8> + std::cmp::PartialEq + Send + Sync,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
-        let original_image = old_image.get_image().clone();
+        let original_image: Vec<&[T]> = old_image
+            .get_samples()
+            .chunks_exact(old_image.get_channels() as usize)
+            .collect();
 
-        let new_pixel: Vec<Pixels<T>> = (0..old_image.get_height())
+        let new_pixel: Vec<&[T]> = (0..old_image.get_height())
             .into_par_iter()
             .flat_map(|y_index| {
-                let mut vec_slice: Vec<Pixels<T>> = Vec::from_iter(
+                let mut vec_slice: Vec<&[T]> = Vec::from_iter(
                     original_image[(y_index * old_image.get_width()) as usize
                         ..((y_index * old_image.get_width()) + old_image.get_width()) as usize]
                         .iter()
@@ -84,19 +102,29 @@ where
                 vec_slice.reverse();
                 vec_slice
             })
-            .collect::<Vec<Pixels<T>>>();
+            .collect::<Vec<&[T]>>();
 
-        let flipped_image: Images<T> = Images::new(
+        let flipped_image: Images<T> = Images::from_samples(
             old_image.get_width(),
             old_image.get_height(),
             old_image.get_channels(),
-            new_pixel.clone(),
+            new_pixel.concat(),
         );
 
         flipped_image
     }
+
+    fn fingerprint(&self) -> String {
+        "flip_horizontal".to_string()
+    }
+
+    fn output_size(&self, input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some(input_size)
+    }
 }
 
+/// Turns the image a quarter clockwise, swapping width and height
+#[derive(Debug)]
 pub struct Flip90Left {}
 
 impl Default for Flip90Left {
@@ -116,34 +144,47 @@ where
     T: Copy + Clone + From<u8> + std::cmp::PartialEq + Send + Sync,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
-        let original_image = old_image.get_image().clone();
+        let original_image: Vec<&[T]> = old_image
+            .get_samples()
+            .chunks_exact(old_image.get_channels() as usize)
+            .collect();
 
-        let new_pixel: Vec<Pixels<T>> = (0..old_image.get_width())
+        let new_pixel: Vec<&[T]> = (0..old_image.get_width())
             .into_par_iter()
             .flat_map(|x_index| {
-                let mut vec_slice: Vec<Pixels<T>> = original_image
+                let mut vec_slice: Vec<&[T]> = original_image
                     .iter()
                     .skip(x_index as usize)
-                    .step_by(old_image.get_height() as usize)
+                    .step_by(old_image.get_width() as usize)
                     .cloned()
                     .collect();
 
                 vec_slice.reverse();
                 vec_slice
             })
-            .collect::<Vec<Pixels<T>>>();
+            .collect::<Vec<&[T]>>();
 
-        let flipped_image: Images<T> = Images::new(
-            old_image.get_width(),
+        let flipped_image: Images<T> = Images::from_samples(
             old_image.get_height(),
+            old_image.get_width(),
             old_image.get_channels(),
-            new_pixel.clone(),
+            new_pixel.concat(),
         );
 
         flipped_image
     }
+
+    fn fingerprint(&self) -> String {
+        "rotate_left".to_string()
+    }
+
+    fn output_size(&self, input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some((input_size.1, input_size.0))
+    }
 }
 
+/// Transposes the image, mirroring it along the main diagonal and swapping width and height
+#[derive(Debug)]
 pub struct Flip90Right {}
 
 impl Default for Flip90Right {
@@ -163,29 +204,61 @@ where
     T: Copy + Clone + From<u8> + std::cmp::PartialEq + Send + Sync,
 {
     fn apply(&self, old_image: &Images<T>) -> Images<T> {
-        let original_image: Vec<Pixels<T>> = old_image.get_image().clone();
+        let original_image: Vec<&[T]> = old_image
+            .get_samples()
+            .chunks_exact(old_image.get_channels() as usize)
+            .collect();
 
         let new_pixel = (0..old_image.get_width())
             .into_par_iter()
             .flat_map(|x_index| {
-                let vec_slice: Vec<Pixels<T>> = original_image
+                let vec_slice: Vec<&[T]> = original_image
                     .iter()
                     .skip(x_index as usize)
-                    .step_by(old_image.get_height() as usize)
+                    .step_by(old_image.get_width() as usize)
                     .cloned()
                     .collect();
 
                 vec_slice
             })
-            .collect::<Vec<Pixels<T>>>();
+            .collect::<Vec<&[T]>>();
 
-        let flipped_image: Images<T> = Images::new(
-            old_image.get_width(),
+        let flipped_image: Images<T> = Images::from_samples(
             old_image.get_height(),
+            old_image.get_width(),
             old_image.get_channels(),
-            new_pixel.clone(),
+            new_pixel.concat(),
         );
 
         flipped_image
     }
+
+    fn fingerprint(&self) -> String {
+        "rotate_right".to_string()
+    }
+
+    fn output_size(&self, input_size: (u32, u32)) -> Option<(u32, u32)> {
+        Some((input_size.1, input_size.0))
+    }
+}
+
+/// Operations that turn an image stored with the given EXIF orientation (1 to 8) upright.
+/// Orientation 1 and unknown values need none.
+pub fn orientation_operations<T>(orientation: u16) -> Vec<Box<dyn Operation<T>>>
+where
+    T: Copy + Clone + From<u8> + std::cmp::PartialEq + Send + Sync + 'static,
+{
+    match orientation {
+        2 => vec![Box::new(FlipHorizontal::new())],
+        3 => vec![Box::new(FlipVertical::new())],
+        4 => vec![
+            Box::new(FlipVertical::new()),
+            Box::new(FlipHorizontal::new()),
+        ],
+        5 => vec![Box::new(Flip90Right::new())],
+        6 => vec![Box::new(Flip90Left::new())],
+        7 => vec![Box::new(Flip90Right::new()), Box::new(FlipVertical::new())],
+        8 => vec![Box::new(Flip90Left::new()), Box::new(FlipVertical::new())],
+        _ => vec![],
+    }
 }
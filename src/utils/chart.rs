@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+use crate::core::{image::Images, sample::Sample};
+
+use super::{image_io::image_writer, statistics::compute_histogram};
+
+/// Space around the plot area, in pixels
+const MARGIN: u32 = 10;
+
+/// Size and scaling of a histogram chart, 512 x 256 pixels by default
+#[derive(Debug, Clone, Copy)]
+pub struct ChartOptions {
+    width: u32,
+    height: u32,
+    log_scale: bool,
+    cumulative: bool,
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        Self {
+            width: 512,
+            height: 256,
+            log_scale: false,
+            cumulative: false,
+        }
+    }
+}
+
+impl ChartOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width.max(2 * MARGIN + 2);
+        self.height = height.max(2 * MARGIN + 2);
+        self
+    }
+
+    /// Scales the counts by `ln(1 + count)`, so rare intensities stay visible
+    pub fn with_log_scale(mut self, log_scale: bool) -> Self {
+        self.log_scale = log_scale;
+        self
+    }
+
+    /// Plots the number of samples up to each intensity instead of at each intensity
+    pub fn with_cumulative(mut self, cumulative: bool) -> Self {
+        self.cumulative = cumulative;
+        self
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+/// One curve of a chart, with its RGBA color
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartSeries {
+    name: &'static str,
+    color: [u8; 4],
+    counts: [u32; 256],
+}
+
+impl ChartSeries {
+    pub fn new(name: &'static str, color: [u8; 4], counts: [u32; 256]) -> Self {
+        Self {
+            name,
+            color,
+            counts,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn get_counts(&self) -> &[u32; 256] {
+        &self.counts
+    }
+}
+
+const RED: [u8; 4] = [220, 50, 40, 255];
+const GREEN: [u8; 4] = [40, 160, 60, 255];
+const BLUE: [u8; 4] = [40, 90, 220, 255];
+const LUMINANCE: [u8; 4] = [60, 60, 60, 255];
+const BACKGROUND: [u8; 4] = [255, 255, 255, 255];
+const AXIS: [u8; 4] = [160, 160, 160, 255];
+
+/// Histograms drawn as overlaid curves over the 256 intensity levels
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramChart {
+    series: Vec<ChartSeries>,
+}
+
+impl HistogramChart {
+    /// Red, green and blue histograms of the image, with a luminance curve (Rec. 709 weights)
+    pub fn new<T>(image: &Images<T>) -> Self
+    where
+        T: Sample,
+    {
+        let mut luminance = [0_u32; 256];
+        for pix in image.get_image().iter() {
+            let value = 0.2126 * pix.get_red().to_unit()
+                + 0.7152 * pix.get_green().to_unit()
+                + 0.0722 * pix.get_blue().to_unit();
+            luminance[u8::from_unit(value) as usize] += 1;
+        }
+
+        let mut chart = Self::from_histograms(&compute_histogram(image));
+        chart
+            .series
+            .push(ChartSeries::new("luminance", LUMINANCE, luminance));
+
+        chart
+    }
+
+    /// Chart of the red, green and blue histograms returned by `compute_histogram`
+    pub fn from_histograms(histograms: &[HashMap<u8, u32>]) -> Self {
+        let series = histograms
+            .iter()
+            .zip([("red", RED), ("green", GREEN), ("blue", BLUE)])
+            .map(|(histogram, (name, color))| {
+                let mut counts = [0_u32; 256];
+                for (intensity, count) in histogram.iter() {
+                    counts[*intensity as usize] = *count;
+                }
+                ChartSeries::new(name, color, counts)
+            })
+            .collect();
+
+        Self { series }
+    }
+
+    pub fn from_series(series: Vec<ChartSeries>) -> Self {
+        Self { series }
+    }
+
+    pub fn get_series(&self) -> &[ChartSeries] {
+        &self.series
+    }
+
+    /// Points of every curve in chart coordinates, with y growing downwards
+    fn curves(&self, options: &ChartOptions) -> Vec<Vec<(f64, f64)>> {
+        let values: Vec<Vec<f64>> = self
+            .series
+            .iter()
+            .map(|series| {
+                let mut total = 0.0;
+                series
+                    .counts
+                    .iter()
+                    .map(|count| {
+                        let value = match options.cumulative {
+                            true => {
+                                total += *count as f64;
+                                total
+                            }
+                            false => *count as f64,
+                        };
+                        match options.log_scale {
+                            true => value.ln_1p(),
+                            false => value,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let max_value = values
+            .iter()
+            .flatten()
+            .fold(0.0_f64, |max, value| max.max(*value))
+            .max(f64::MIN_POSITIVE);
+
+        let plot_width = (options.width - 2 * MARGIN - 1) as f64;
+        let plot_height = (options.height - 2 * MARGIN - 1) as f64;
+        values
+            .iter()
+            .map(|values| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(intensity, value)| {
+                        (
+                            MARGIN as f64 + intensity as f64 / 255.0 * plot_width,
+                            MARGIN as f64 + plot_height * (1.0 - value / max_value),
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn render_svg(&self, options: &ChartOptions) -> String {
+        let (width, height) = (options.width, options.height);
+        let bottom = height - MARGIN - 1;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            width, height, width, height
+        );
+        svg.push_str(&format!(
+            "  <rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            width,
+            height,
+            svg_color(BACKGROUND)
+        ));
+        svg.push_str(&format!(
+            "  <polyline points=\"{},{} {},{} {},{}\" fill=\"none\" stroke=\"{}\"/>\n",
+            MARGIN,
+            MARGIN,
+            MARGIN,
+            bottom,
+            width - MARGIN - 1,
+            bottom,
+            svg_color(AXIS)
+        ));
+        for (series, curve) in self.series.iter().zip(self.curves(options)) {
+            let points: Vec<String> = curve
+                .iter()
+                .map(|(x, y)| format!("{:.2},{:.2}", x, y))
+                .collect();
+            svg.push_str(&format!(
+                "  <polyline class=\"{}\" points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"/>\n",
+                series.name,
+                points.join(" "),
+                svg_color(series.color)
+            ));
+        }
+        for (index, series) in self.series.iter().enumerate() {
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"11\" text-anchor=\"end\" fill=\"{}\">{}</text>\n",
+                width - MARGIN - 2,
+                MARGIN + 12 * (index as u32 + 1),
+                svg_color(series.color),
+                series.name
+            ));
+        }
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+
+    /// Draws the chart into an RGBA image, without the legend of the SVG chart
+    pub fn render_image(&self, options: &ChartOptions) -> Images<u8> {
+        let (width, height) = (options.width, options.height);
+        let mut samples: Vec<u8> = BACKGROUND
+            .iter()
+            .copied()
+            .cycle()
+            .take(width as usize * height as usize * 4)
+            .collect();
+        let mut plot = |x: i64, y: i64, color: [u8; 4]| {
+            if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+                let index = (y as usize * width as usize + x as usize) * 4;
+                samples[index..index + 4].copy_from_slice(&color);
+            }
+        };
+
+        let bottom = (height - MARGIN - 1) as i64;
+        draw_line(
+            (MARGIN as i64, MARGIN as i64),
+            (MARGIN as i64, bottom),
+            AXIS,
+            &mut plot,
+        );
+        draw_line(
+            (MARGIN as i64, bottom),
+            ((width - MARGIN - 1) as i64, bottom),
+            AXIS,
+            &mut plot,
+        );
+        for (series, curve) in self.series.iter().zip(self.curves(options)) {
+            for segment in curve.windows(2) {
+                let from = (segment[0].0.round() as i64, segment[0].1.round() as i64);
+                let to = (segment[1].0.round() as i64, segment[1].1.round() as i64);
+                draw_line(from, to, series.color, &mut plot);
+            }
+        }
+
+        Images::from_samples(width, height, 4, samples)
+    }
+
+    /// Writes the chart as SVG for a `.svg` path, or as an image in the format of the extension
+    pub fn save(
+        &self,
+        filepath: &str,
+        options: &ChartOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if filepath.to_ascii_lowercase().ends_with(".svg") {
+            std::fs::write(filepath, self.render_svg(options))?;
+            return Ok(());
+        }
+
+        image_writer(filepath, &self.render_image(options))
+    }
+}
+
+fn svg_color(color: [u8; 4]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Bresenham line between two pixels, both included
+fn draw_line<F>(from: (i64, i64), to: (i64, i64), color: [u8; 4], plot: &mut F)
+where
+    F: FnMut(i64, i64, [u8; 4]),
+{
+    let (mut x, mut y) = from;
+    let delta_x = (to.0 - x).abs();
+    let delta_y = -(to.1 - y).abs();
+    let step_x = if x < to.0 { 1 } else { -1 };
+    let step_y = if y < to.1 { 1 } else { -1 };
+    let mut error = delta_x + delta_y;
+
+    loop {
+        plot(x, y, color);
+        if (x, y) == to {
+            break;
+        }
+        let doubled = 2 * error;
+        if doubled >= delta_y {
+            error += delta_y;
+            x += step_x;
+        }
+        if doubled <= delta_x {
+            error += delta_x;
+            y += step_y;
+        }
+    }
+}
@@ -1,43 +1,1027 @@
-use crate::core::image::Images;
+use rayon::{iter::ParallelIterator, slice::ParallelSlice};
 
-pub fn rgba_to_hsv<T>(image: Images<T>) -> Vec<(f64, f64, f64)>
+use crate::core::{
+    cache::fnv1a, image::Images, operations::Operation, pixel::Pixels, sample::Sample,
+};
+
+// D65 reference white used by XYZ, CIELAB and CIELUV
+const WHITE_X: f64 = 0.95047;
+const WHITE_Y: f64 = 1.0;
+const WHITE_Z: f64 = 1.08883;
+
+// CIE constants: epsilon = (6/29)^3 and kappa = (29/3)^3
+const CIE_EPSILON: f64 = 216.0 / 24389.0;
+const CIE_KAPPA: f64 = 24389.0 / 27.0;
+
+/// A color space that can be converted to and from gamma encoded sRGB.
+/// RGB components are normalized to 0.0..=1.0.
+pub trait ColorSpace: Copy + Clone + Send + Sync {
+    fn from_rgb(red: f64, green: f64, blue: f64) -> Self;
+    fn to_rgb(&self) -> (f64, f64, f64);
+}
+
+/// Hue in degrees (0.0..360.0), saturation and value in 0.0..=1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub hue: f64,
+    pub saturation: f64,
+    pub value: f64,
+}
+
+/// Hue in degrees (0.0..360.0), saturation and lightness in 0.0..=1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub hue: f64,
+    pub saturation: f64,
+    pub lightness: f64,
+}
+
+/// Full range BT.601 (JPEG): luma in 0.0..=1.0, chroma differences in -0.5..=0.5
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YCbCr {
+    pub y: f64,
+    pub cb: f64,
+    pub cr: f64,
+}
+
+/// CIE 1931 XYZ relative to the D65 white point, with Y = 1.0 for white
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Xyz {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// CIELAB (D65): lightness in 0.0..=100.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+/// CIELUV (D65): lightness in 0.0..=100.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Luv {
+    pub l: f64,
+    pub u: f64,
+    pub v: f64,
+}
+
+/// OKLab: perceptual lightness in 0.0..=1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+/// sRGB electro-optical transfer function: gamma encoded value to linear light
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Inverse of `srgb_to_linear`: linear light to gamma encoded value
+pub fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Gamma encoded sample to linear light in 0.0..=1.0
+pub fn sample_to_linear<T>(value: T) -> f64
 where
-    T: Copy + Clone + From<u8> + Into<f64> + std::cmp::PartialEq,
+    T: Sample,
 {
-    let mut hsv_image: Vec<(f64, f64, f64)> = Vec::new();
-    for pix in image.get_image() {
-        let r = pix.get_red().into() / 255.0;
-        let g = pix.get_green().into() / 255.0;
-        let b = pix.get_blue().into() / 255.0;
+    srgb_to_linear(value.to_unit())
+}
+
+/// Linear light in 0.0..=1.0 to a gamma encoded sample
+pub fn linear_to_sample<T>(value: f64) -> T
+where
+    T: Sample,
+{
+    T::from_unit(linear_to_srgb(value.clamp(0.0, 1.0)))
+}
+
+fn hue_from_rgb(red: f64, green: f64, blue: f64, max_val: f64, delta: f64) -> f64 {
+    if delta == 0.0 {
+        return 0.0;
+    }
+    let mut hue = if max_val == red {
+        60.0 * (green - blue) / delta
+    } else if max_val == green {
+        60.0 * (blue - red) / delta + 120.0
+    } else {
+        60.0 * (red - green) / delta + 240.0
+    };
+    if hue < 0.0 {
+        hue += 360.0;
+    }
 
-        let min_val = (r.min(g)).min(b);
-        let max_val = (r.max(g)).max(b);
+    hue
+}
+
+/// Shared by HSV and HSL: RGB from hue, chroma and the offset added to every channel
+fn rgb_from_hue_chroma(hue: f64, chroma: f64, offset: f64) -> (f64, f64, f64) {
+    let hue_sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (hue_sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue_sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    (r + offset, g + offset, b + offset)
+}
+
+impl ColorSpace for Hsv {
+    fn from_rgb(red: f64, green: f64, blue: f64) -> Self {
+        let min_val = (red.min(green)).min(blue);
+        let max_val = (red.max(green)).max(blue);
         let delta = max_val - min_val;
 
-        let mut hsv: (f64, f64, f64) = (0.0, 0.0, 0.0);
-        if delta == 0.0 {
-            hsv.0 = 0.0;
-            hsv.1 = 0.0;
-            hsv.2 = max_val;
-        } else {
-            if max_val == r {
-                hsv.0 = 60.0 * (g - b) / delta;
-            } else if max_val == g {
-                hsv.0 = 60.0 * (b - r) / delta + 120.0;
+        Self {
+            hue: hue_from_rgb(red, green, blue, max_val, delta),
+            saturation: if max_val == 0.0 { 0.0 } else { delta / max_val },
+            value: max_val,
+        }
+    }
+
+    fn to_rgb(&self) -> (f64, f64, f64) {
+        let chroma = self.value * self.saturation;
+        rgb_from_hue_chroma(self.hue, chroma, self.value - chroma)
+    }
+}
+
+impl ColorSpace for Hsl {
+    fn from_rgb(red: f64, green: f64, blue: f64) -> Self {
+        let min_val = (red.min(green)).min(blue);
+        let max_val = (red.max(green)).max(blue);
+        let delta = max_val - min_val;
+        let lightness = (max_val + min_val) / 2.0;
+
+        Self {
+            hue: hue_from_rgb(red, green, blue, max_val, delta),
+            saturation: if delta == 0.0 {
+                0.0
             } else {
-                hsv.0 = 60.0 * (r - g) / delta + 240.0;
+                delta / (1.0 - (2.0 * lightness - 1.0).abs())
+            },
+            lightness,
+        }
+    }
+
+    fn to_rgb(&self) -> (f64, f64, f64) {
+        let chroma = (1.0 - (2.0 * self.lightness - 1.0).abs()) * self.saturation;
+        rgb_from_hue_chroma(self.hue, chroma, self.lightness - chroma / 2.0)
+    }
+}
+
+impl ColorSpace for YCbCr {
+    fn from_rgb(red: f64, green: f64, blue: f64) -> Self {
+        let y = 0.299 * red + 0.587 * green + 0.114 * blue;
+        Self {
+            y,
+            cb: (blue - y) / 1.772,
+            cr: (red - y) / 1.402,
+        }
+    }
+
+    fn to_rgb(&self) -> (f64, f64, f64) {
+        let red = self.y + 1.402 * self.cr;
+        let blue = self.y + 1.772 * self.cb;
+        let green = (self.y - 0.299 * red - 0.114 * blue) / 0.587;
+
+        (red, green, blue)
+    }
+}
+
+impl ColorSpace for Xyz {
+    fn from_rgb(red: f64, green: f64, blue: f64) -> Self {
+        let (r, g, b) = (
+            srgb_to_linear(red),
+            srgb_to_linear(green),
+            srgb_to_linear(blue),
+        );
+        Self {
+            x: 0.4124564 * r + 0.3575761 * g + 0.1804375 * b,
+            y: 0.2126729 * r + 0.7151522 * g + 0.0721750 * b,
+            z: 0.0193339 * r + 0.1191920 * g + 0.9503041 * b,
+        }
+    }
+
+    fn to_rgb(&self) -> (f64, f64, f64) {
+        let r = 3.2404542 * self.x - 1.5371385 * self.y - 0.4985314 * self.z;
+        let g = -0.9692660 * self.x + 1.8760108 * self.y + 0.0415560 * self.z;
+        let b = 0.0556434 * self.x - 0.2040259 * self.y + 1.0572252 * self.z;
+
+        (linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+    }
+}
+
+fn lab_f(t: f64) -> f64 {
+    if t > CIE_EPSILON {
+        t.cbrt()
+    } else {
+        (CIE_KAPPA * t + 16.0) / 116.0
+    }
+}
+
+fn lab_f_inverse(t: f64) -> f64 {
+    let cube = t.powi(3);
+    if cube > CIE_EPSILON {
+        cube
+    } else {
+        (116.0 * t - 16.0) / CIE_KAPPA
+    }
+}
+
+impl ColorSpace for Lab {
+    fn from_rgb(red: f64, green: f64, blue: f64) -> Self {
+        let xyz = Xyz::from_rgb(red, green, blue);
+        let fx = lab_f(xyz.x / WHITE_X);
+        let fy = lab_f(xyz.y / WHITE_Y);
+        let fz = lab_f(xyz.z / WHITE_Z);
+
+        Self {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    fn to_rgb(&self) -> (f64, f64, f64) {
+        let fy = (self.l + 16.0) / 116.0;
+        let fx = fy + self.a / 500.0;
+        let fz = fy - self.b / 200.0;
+
+        Xyz {
+            x: WHITE_X * lab_f_inverse(fx),
+            y: WHITE_Y * lab_f_inverse(fy),
+            z: WHITE_Z * lab_f_inverse(fz),
+        }
+        .to_rgb()
+    }
+}
+
+/// Chromaticity coordinates (u', v') of an XYZ color
+fn uv_prime(x: f64, y: f64, z: f64) -> (f64, f64) {
+    let denominator = x + 15.0 * y + 3.0 * z;
+    if denominator == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    (4.0 * x / denominator, 9.0 * y / denominator)
+}
+
+impl ColorSpace for Luv {
+    fn from_rgb(red: f64, green: f64, blue: f64) -> Self {
+        let xyz = Xyz::from_rgb(red, green, blue);
+        let (u_white, v_white) = uv_prime(WHITE_X, WHITE_Y, WHITE_Z);
+        let (u_prime, v_prime) = uv_prime(xyz.x, xyz.y, xyz.z);
+
+        let y_relative = xyz.y / WHITE_Y;
+        let l = if y_relative > CIE_EPSILON {
+            116.0 * y_relative.cbrt() - 16.0
+        } else {
+            CIE_KAPPA * y_relative
+        };
+        if l == 0.0 {
+            return Self {
+                l: 0.0,
+                u: 0.0,
+                v: 0.0,
+            };
+        }
+
+        Self {
+            l,
+            u: 13.0 * l * (u_prime - u_white),
+            v: 13.0 * l * (v_prime - v_white),
+        }
+    }
+
+    fn to_rgb(&self) -> (f64, f64, f64) {
+        if self.l <= 0.0 {
+            return (0.0, 0.0, 0.0);
+        }
+        let (u_white, v_white) = uv_prime(WHITE_X, WHITE_Y, WHITE_Z);
+        let u_prime = self.u / (13.0 * self.l) + u_white;
+        let v_prime = self.v / (13.0 * self.l) + v_white;
+
+        let y = if self.l > CIE_KAPPA * CIE_EPSILON {
+            WHITE_Y * ((self.l + 16.0) / 116.0).powi(3)
+        } else {
+            WHITE_Y * self.l / CIE_KAPPA
+        };
+        let x = y * 9.0 * u_prime / (4.0 * v_prime);
+        let z = y * (12.0 - 3.0 * u_prime - 20.0 * v_prime) / (4.0 * v_prime);
+
+        Xyz { x, y, z }.to_rgb()
+    }
+}
+
+impl ColorSpace for Oklab {
+    // Matrices from https://bottosson.github.io/posts/oklab/
+    fn from_rgb(red: f64, green: f64, blue: f64) -> Self {
+        let (r, g, b) = (
+            srgb_to_linear(red),
+            srgb_to_linear(green),
+            srgb_to_linear(blue),
+        );
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+        Self {
+            l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        }
+    }
+
+    fn to_rgb(&self) -> (f64, f64, f64) {
+        let l = (self.l + 0.3963377774 * self.a + 0.2158037573 * self.b).powi(3);
+        let m = (self.l - 0.1055613458 * self.a - 0.0638541728 * self.b).powi(3);
+        let s = (self.l - 0.0894841775 * self.a - 1.2914855480 * self.b).powi(3);
+
+        let r = 4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s;
+        let g = -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s;
+        let b = -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s;
+
+        (linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+    }
+}
+
+/// An image whose pixels are stored in color space `C`, with alpha kept aside (0.0..=1.0)
+/// so it can be converted back into `Images` without loss.
+#[derive(Debug, Clone)]
+pub struct ColorImage<C>
+where
+    C: ColorSpace,
+{
+    width: u32,
+    height: u32,
+    channels: u8,
+    color_data: Vec<C>,
+    alpha_data: Vec<f64>,
+}
+
+impl<C> ColorImage<C>
+where
+    C: ColorSpace,
+{
+    pub fn new(
+        width: u32,
+        height: u32,
+        channels: u8,
+        color_data: Vec<C>,
+        alpha_data: Vec<f64>,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            channels,
+            color_data,
+            alpha_data,
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_channels(&self) -> u8 {
+        self.channels
+    }
+
+    pub fn get_colors(&self) -> &[C] {
+        &self.color_data
+    }
+
+    pub fn get_alphas(&self) -> &[f64] {
+        &self.alpha_data
+    }
+
+    pub fn get_colors_mut(&mut self) -> &mut [C] {
+        &mut self.color_data
+    }
+}
+
+/// Converts a single pixel into color space `C`, ignoring alpha
+pub fn pixel_to_color<C, T>(pix: &Pixels<T>) -> C
+where
+    C: ColorSpace,
+    T: Sample,
+{
+    C::from_rgb(
+        pix.get_red().to_unit(),
+        pix.get_green().to_unit(),
+        pix.get_blue().to_unit(),
+    )
+}
+
+/// Converts a color back into a pixel with the given alpha. Out of gamut values are clamped.
+pub fn color_to_pixel<C, T>(color: &C, alpha: T) -> Pixels<T>
+where
+    C: ColorSpace,
+    T: Sample,
+{
+    let (red, green, blue) = color.to_rgb();
+    Pixels::new(
+        T::from_unit(red),
+        T::from_unit(green),
+        T::from_unit(blue),
+        alpha,
+    )
+}
+
+pub fn convert_to_color_space<C, T>(image: &Images<T>) -> ColorImage<C>
+where
+    C: ColorSpace,
+    T: Sample,
+{
+    let pixels = image.get_image();
+    let color_data = pixels.iter().map(pixel_to_color).collect::<Vec<C>>();
+    let alpha_data = pixels
+        .iter()
+        .map(|pix| pix.get_alpha().to_unit())
+        .collect::<Vec<f64>>();
+
+    ColorImage::new(
+        image.get_width(),
+        image.get_height(),
+        image.get_channels(),
+        color_data,
+        alpha_data,
+    )
+}
+
+pub fn convert_from_color_space<C, T>(image: &ColorImage<C>) -> Images<T>
+where
+    C: ColorSpace,
+    T: Sample,
+{
+    let image_data = image
+        .get_colors()
+        .iter()
+        .zip(image.get_alphas().iter())
+        .map(|(color, alpha)| color_to_pixel(color, T::from_unit(*alpha)))
+        .collect::<Vec<Pixels<T>>>();
+
+    Images::new(
+        image.get_width(),
+        image.get_height(),
+        image.get_channels(),
+        image_data,
+    )
+}
+
+pub fn rgba_to_hsv<T>(image: &Images<T>) -> ColorImage<Hsv>
+where
+    T: Sample,
+{
+    convert_to_color_space(image)
+}
+
+pub fn hsv_to_rgba<T>(image: &ColorImage<Hsv>) -> Images<T>
+where
+    T: Sample,
+{
+    convert_from_color_space(image)
+}
+
+// D50 illuminant of the ICC profile connection space
+const ICC_D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+// Colorants of sRGB adapted to D50 with the Bradford transform, as stored in sRGB profiles
+const SRGB_D50_COLORANTS: [[f64; 3]; 3] = [
+    [0.4360747, 0.2225045, 0.0139322],
+    [0.3850649, 0.7168786, 0.0971045],
+    [0.1430804, 0.0606169, 0.7141733],
+];
+
+/// Transfer function of an ICC profile channel, from encoded value to linear light
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToneCurve {
+    Identity,
+    Gamma(f64),
+    /// Evenly spaced samples in 0.0..=1.0, linearly interpolated
+    Table(Vec<f64>),
+    /// ICC parametric curve, function types 0 to 4 with their parameters (g, a, b, c, d, e, f)
+    Parametric {
+        function_type: u16,
+        params: Vec<f64>,
+    },
+}
+
+impl ToneCurve {
+    pub fn to_linear(&self, value: f64) -> f64 {
+        let value = value.clamp(0.0, 1.0);
+        match self {
+            ToneCurve::Identity => value,
+            ToneCurve::Gamma(gamma) => value.powf(*gamma),
+            ToneCurve::Table(table) => {
+                if table.len() < 2 {
+                    return table.first().copied().unwrap_or(value);
+                }
+                let position = value * (table.len() - 1) as f64;
+                let index = (position.floor() as usize).min(table.len() - 2);
+                let fraction = position - index as f64;
+                table[index] + (table[index + 1] - table[index]) * fraction
+            }
+            ToneCurve::Parametric {
+                function_type,
+                params,
+            } => {
+                let param = |index: usize| params.get(index).copied().unwrap_or(0.0);
+                let (g, a, b, c, d, e, f) = (
+                    param(0),
+                    param(1),
+                    param(2),
+                    param(3),
+                    param(4),
+                    param(5),
+                    param(6),
+                );
+                let power = |base: f64| base.max(0.0).powf(g);
+                match function_type {
+                    0 => power(value),
+                    1 if value >= -b / a => power(a * value + b),
+                    1 => 0.0,
+                    2 if value >= -b / a => power(a * value + b) + c,
+                    2 => c,
+                    3 if value >= d => power(a * value + b),
+                    3 => c * value,
+                    _ if value >= d => power(a * value + b) + e,
+                    _ => c * value + f,
+                }
+            }
+        }
+    }
+}
+
+/// Matrix/TRC ICC profile (RGB or gray), the kind used for Display P3, Adobe RGB and sRGB.
+/// Colorants are the XYZ columns of red, green and blue in the D50 connection space,
+/// gray profiles have none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccProfile {
+    description: String,
+    colorants: Option<[[f64; 3]; 3]>,
+    tone_curves: [ToneCurve; 3],
+}
+
+fn icc_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn icc_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn icc_s15_fixed16(bytes: &[u8], offset: usize) -> Option<f64> {
+    Some(icc_u32(bytes, offset)? as i32 as f64 / 65536.0)
+}
+
+fn to_s15_fixed16(value: f64) -> [u8; 4] {
+    ((value * 65536.0).round() as i32).to_be_bytes()
+}
+
+fn parse_xyz_tag(tag: &[u8]) -> Option<[f64; 3]> {
+    if tag.get(0..4)? != b"XYZ " {
+        return None;
+    }
+    Some([
+        icc_s15_fixed16(tag, 8)?,
+        icc_s15_fixed16(tag, 12)?,
+        icc_s15_fixed16(tag, 16)?,
+    ])
+}
+
+fn parse_curve_tag(tag: &[u8]) -> Option<ToneCurve> {
+    match tag.get(0..4)? {
+        b"curv" => {
+            let count = icc_u32(tag, 8)? as usize;
+            match count {
+                0 => Some(ToneCurve::Identity),
+                1 => Some(ToneCurve::Gamma(icc_u16(tag, 12)? as f64 / 256.0)),
+                _ => (0..count)
+                    .map(|index| Some(icc_u16(tag, 12 + index * 2)? as f64 / 65535.0))
+                    .collect::<Option<Vec<f64>>>()
+                    .map(ToneCurve::Table),
+            }
+        }
+        b"para" => {
+            let function_type = icc_u16(tag, 8)?;
+            let param_count = match function_type {
+                0 => 1,
+                1 => 3,
+                2 => 4,
+                3 => 5,
+                4 => 7,
+                _ => return None,
+            };
+            let params = (0..param_count)
+                .map(|index| icc_s15_fixed16(tag, 12 + index * 4))
+                .collect::<Option<Vec<f64>>>()?;
+            Some(ToneCurve::Parametric {
+                function_type,
+                params,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn curve_tag(curve: &ToneCurve) -> Vec<u8> {
+    let mut tag = Vec::new();
+    match curve {
+        ToneCurve::Parametric {
+            function_type,
+            params,
+        } => {
+            tag.extend(b"para\0\0\0\0");
+            tag.extend(function_type.to_be_bytes());
+            tag.extend([0, 0]);
+            params
+                .iter()
+                .for_each(|param| tag.extend(to_s15_fixed16(*param)));
+        }
+        _ => {
+            let entries: Vec<u16> = match curve {
+                ToneCurve::Gamma(gamma) => vec![(gamma * 256.0).round() as u16],
+                ToneCurve::Table(table) => table
+                    .iter()
+                    .map(|value| (value.clamp(0.0, 1.0) * 65535.0).round() as u16)
+                    .collect(),
+                _ => vec![],
+            };
+            tag.extend(b"curv\0\0\0\0");
+            tag.extend((entries.len() as u32).to_be_bytes());
+            entries
+                .iter()
+                .for_each(|entry| tag.extend(entry.to_be_bytes()));
+        }
+    }
+
+    tag
+}
+
+fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    xyz.iter()
+        .for_each(|value| tag.extend(to_s15_fixed16(*value)));
+    tag
+}
+
+/// Multi localized unicode tag with a single English text
+fn text_tag(text: &str) -> Vec<u8> {
+    let utf16: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    let mut tag = b"mluc\0\0\0\0".to_vec();
+    tag.extend(1_u32.to_be_bytes());
+    tag.extend(12_u32.to_be_bytes());
+    tag.extend(b"enUS");
+    tag.extend((utf16.len() as u32).to_be_bytes());
+    tag.extend(28_u32.to_be_bytes());
+    tag.extend(utf16);
+    tag
+}
+
+/// Text of a `desc` (version 2) or `mluc` (version 4) tag, first record only
+fn parse_text_tag(tag: &[u8]) -> Option<String> {
+    match tag.get(0..4)? {
+        b"desc" => {
+            let count = icc_u32(tag, 8)? as usize;
+            let text = tag.get(12..12 + count)?;
+            Some(
+                String::from_utf8_lossy(text)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            )
+        }
+        b"mluc" => {
+            let length = icc_u32(tag, 20)? as usize;
+            let offset = icc_u32(tag, 24)? as usize;
+            let utf16: Vec<u16> = tag
+                .get(offset..offset + length)?
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            Some(String::from_utf16_lossy(&utf16))
+        }
+        _ => None,
+    }
+}
+
+fn invert_matrix(m: [[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if det.abs() < f64::EPSILON {
+        return None;
+    }
+    let cofactor = |row: usize, col: usize| {
+        let rows: Vec<usize> = (0..3).filter(|index| *index != row).collect();
+        let cols: Vec<usize> = (0..3).filter(|index| *index != col).collect();
+        let minor =
+            m[rows[0]][cols[0]] * m[rows[1]][cols[1]] - m[rows[0]][cols[1]] * m[rows[1]][cols[0]];
+        if (row + col).is_multiple_of(2) {
+            minor
+        } else {
+            -minor
+        }
+    };
+
+    let mut inverse = [[0.0; 3]; 3];
+    for (row, inverse_row) in inverse.iter_mut().enumerate() {
+        for (col, value) in inverse_row.iter_mut().enumerate() {
+            *value = cofactor(col, row) / det;
+        }
+    }
+    Some(inverse)
+}
+
+fn multiply_matrix(lhs: [[f64; 3]; 3], rhs: [[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut product = [[0.0; 3]; 3];
+    for (row, product_row) in product.iter_mut().enumerate() {
+        for (col, value) in product_row.iter_mut().enumerate() {
+            *value = (0..3).map(|index| lhs[row][index] * rhs[index][col]).sum();
+        }
+    }
+    product
+}
+
+/// Rows are X, Y and Z, columns are the red, green and blue colorants
+fn colorant_matrix(colorants: [[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut matrix = [[0.0; 3]; 3];
+    for (channel, colorant) in colorants.iter().enumerate() {
+        for (component, value) in colorant.iter().enumerate() {
+            matrix[component][channel] = *value;
+        }
+    }
+    matrix
+}
+
+impl IccProfile {
+    pub fn new(
+        description: &str,
+        colorants: Option<[[f64; 3]; 3]>,
+        tone_curves: [ToneCurve; 3],
+    ) -> Self {
+        Self {
+            description: description.to_string(),
+            colorants,
+            tone_curves,
+        }
+    }
+
+    /// sRGB IEC61966-2.1
+    pub fn srgb() -> Self {
+        let curve = ToneCurve::Parametric {
+            function_type: 3,
+            params: vec![2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045],
+        };
+        Self::new(
+            "sRGB",
+            Some(SRGB_D50_COLORANTS),
+            [curve.clone(), curve.clone(), curve],
+        )
+    }
+
+    /// sRGB primaries with a linear transfer function
+    pub fn linear_srgb() -> Self {
+        Self::new(
+            "Linear sRGB",
+            Some(SRGB_D50_COLORANTS),
+            [
+                ToneCurve::Identity,
+                ToneCurve::Identity,
+                ToneCurve::Identity,
+            ],
+        )
+    }
+
+    pub fn get_description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn is_gray(&self) -> bool {
+        self.colorants.is_none()
+    }
+
+    /// Parses the colorant and tone curve tags of an RGB or gray matrix/TRC profile.
+    /// Profiles built on lookup tables only are not supported.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        if bytes.get(36..40) != Some(b"acsp".as_slice()) {
+            return Err("Not an ICC profile".into());
+        }
+        let tag_count = icc_u32(bytes, 128).ok_or("Truncated ICC tag table")? as usize;
+        // Entries of 12 bytes follow the count, and the count itself is not trusted
+        if tag_count > (bytes.len() - 132) / 12 {
+            return Err("Truncated ICC tag table".into());
+        }
+        let tag = |signature: &[u8]| -> Option<&[u8]> {
+            (0..tag_count)
+                .map(|index| 132 + index * 12)
+                .find(|entry| bytes.get(*entry..entry + 4) == Some(signature))
+                .and_then(|entry| {
+                    let offset = icc_u32(bytes, entry + 4)? as usize;
+                    let size = icc_u32(bytes, entry + 8)? as usize;
+                    bytes.get(offset..offset + size)
+                })
+        };
+        let description = tag(b"desc").and_then(parse_text_tag).unwrap_or_default();
+        let curve = |signature: &[u8]| -> Result<ToneCurve, Box<dyn std::error::Error>> {
+            tag(signature)
+                .and_then(parse_curve_tag)
+                .ok_or_else(|| "ICC profile has no supported tone curve".into())
+        };
+
+        match bytes.get(16..20) {
+            Some(b"RGB ") => {
+                let colorant = |signature: &[u8]| -> Result<[f64; 3], Box<dyn std::error::Error>> {
+                    tag(signature)
+                        .and_then(parse_xyz_tag)
+                        .ok_or_else(|| "ICC profile has no colorant tags".into())
+                };
+                Ok(Self::new(
+                    &description,
+                    Some([colorant(b"rXYZ")?, colorant(b"gXYZ")?, colorant(b"bXYZ")?]),
+                    [curve(b"rTRC")?, curve(b"gTRC")?, curve(b"bTRC")?],
+                ))
             }
+            Some(b"GRAY") => {
+                let gray = curve(b"kTRC")?;
+                Ok(Self::new(
+                    &description,
+                    None,
+                    [gray.clone(), gray.clone(), gray],
+                ))
+            }
+            _ => Err("Only RGB and gray ICC profiles are supported".into()),
+        }
+    }
 
-            if hsv.0 < 0.0 {
-                hsv.0 += 360.0
+    /// Serializes the profile as an ICC version 4 display profile
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut tags: Vec<(&[u8], Vec<u8>)> = vec![
+            (b"desc", text_tag(&self.description)),
+            (b"wtpt", xyz_tag(ICC_D50)),
+        ];
+        match self.colorants {
+            Some(colorants) => {
+                tags.push((b"rXYZ", xyz_tag(colorants[0])));
+                tags.push((b"gXYZ", xyz_tag(colorants[1])));
+                tags.push((b"bXYZ", xyz_tag(colorants[2])));
+                tags.push((b"rTRC", curve_tag(&self.tone_curves[0])));
+                tags.push((b"gTRC", curve_tag(&self.tone_curves[1])));
+                tags.push((b"bTRC", curve_tag(&self.tone_curves[2])));
             }
+            None => tags.push((b"kTRC", curve_tag(&self.tone_curves[0]))),
+        }
+
+        let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+        let mut data = Vec::new();
+        let data_start = 128 + 4 + tags.len() * 12;
+        for (signature, tag) in tags.iter() {
+            table.extend(*signature);
+            table.extend(((data_start + data.len()) as u32).to_be_bytes());
+            table.extend((tag.len() as u32).to_be_bytes());
+            data.extend(tag);
+            // Tags start on 4 byte boundaries
+            data.resize(data.len().next_multiple_of(4), 0);
+        }
 
-            hsv.1 = delta / max_val;
-            hsv.2 = max_val;
+        let mut header = vec![0; 128];
+        let size = (128 + table.len() + data.len()) as u32;
+        header[0..4].copy_from_slice(&size.to_be_bytes());
+        header[8..12].copy_from_slice(&[4, 0x30, 0, 0]);
+        header[12..16].copy_from_slice(b"mntr");
+        header[16..20].copy_from_slice(if self.is_gray() { b"GRAY" } else { b"RGB " });
+        header[20..24].copy_from_slice(b"XYZ ");
+        header[36..40].copy_from_slice(b"acsp");
+        for (index, value) in ICC_D50.iter().enumerate() {
+            header[68 + index * 4..72 + index * 4].copy_from_slice(&to_s15_fixed16(*value));
         }
 
-        hsv_image.push((hsv.0, hsv.1, hsv.2));
+        [header, table, data].concat()
+    }
+}
+
+/// Color space images are converted into for processing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkingSpace {
+    Srgb,
+    LinearSrgb,
+}
+
+impl WorkingSpace {
+    /// Profile to embed in images stored in this working space
+    pub fn profile(&self) -> IccProfile {
+        match self {
+            WorkingSpace::Srgb => IccProfile::srgb(),
+            WorkingSpace::LinearSrgb => IccProfile::linear_srgb(),
+        }
+    }
+}
+
+/// Converts images tagged with an ICC profile into a working space.
+/// Colors outside the working space gamut are clipped.
+#[derive(Debug)]
+pub struct ConvertToWorkingSpace {
+    source: IccProfile,
+    target: WorkingSpace,
+}
+
+impl ConvertToWorkingSpace {
+    pub fn new(source: IccProfile, target: WorkingSpace) -> Self {
+        Self { source, target }
+    }
+}
+
+impl<T> Operation<T> for ConvertToWorkingSpace
+where
+    T: Sample,
+{
+    fn apply(&self, old_image: &Images<T>) -> Images<T> {
+        let channels = old_image.get_channels() as usize;
+        let color_channels = if channels >= 3 { 3 } else { 1 };
+        // Source linear RGB to XYZ (D50), then XYZ to linear sRGB
+        let matrix = match self.source.colorants {
+            Some(colorants) if color_channels == 3 => {
+                invert_matrix(colorant_matrix(SRGB_D50_COLORANTS))
+                    .map(|to_srgb| multiply_matrix(to_srgb, colorant_matrix(colorants)))
+            }
+            _ => None,
+        };
+        let target = self.target;
+        let encode = |linear: f64| -> T {
+            let linear = linear.clamp(0.0, 1.0);
+            match target {
+                WorkingSpace::Srgb => T::from_unit(linear_to_srgb(linear)),
+                WorkingSpace::LinearSrgb => T::from_unit(linear),
+            }
+        };
+
+        let new_samples = old_image
+            .get_samples()
+            .par_chunks_exact(channels)
+            .flat_map_iter(|pixel| {
+                let linear: Vec<f64> = (0..color_channels)
+                    .map(|channel| {
+                        self.source.tone_curves[channel].to_linear(pixel[channel].to_unit())
+                    })
+                    .collect();
+                let converted: Vec<f64> = match matrix {
+                    Some(matrix) => matrix
+                        .iter()
+                        .map(|row| (0..3).map(|index| row[index] * linear[index]).sum())
+                        .collect(),
+                    None => linear,
+                };
+
+                let mut new_pixel: Vec<T> = converted.into_iter().map(encode).collect();
+                // Alpha is never color managed
+                new_pixel.extend(&pixel[color_channels..]);
+                new_pixel
+            })
+            .collect::<Vec<T>>();
+
+        Images::from_samples(
+            old_image.get_width(),
+            old_image.get_height(),
+            old_image.get_channels(),
+            new_samples,
+        )
+    }
+
+    fn tile_halo(&self) -> Option<u32> {
+        Some(0)
     }
 
-    hsv_image
+    fn fingerprint(&self) -> String {
+        format!(
+            "working_space:{:016x},{:?}",
+            fnv1a(&self.source.to_bytes()),
+            self.target
+        )
+    }
 }
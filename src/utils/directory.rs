@@ -0,0 +1,193 @@
+use std::path::{Path, PathBuf};
+
+use image::ImageFormat;
+use log::{info, warn};
+use tokio::fs;
+
+use crate::core::report::SkippedFile;
+
+/// Which files of an input folder `process_images` picks up.
+/// Patterns support `*` and `?` within a folder, `**` across folders, and `{png,jpg}` alternatives.
+/// Patterns without a `/` match the file name, others match the path relative to the input folder.
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryOptions {
+    recursive: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl DirectoryOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Walks into subfolders, mirroring them in the output folder
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Only processes files matching one of the include patterns, or all files without any
+    pub fn with_include(mut self, pattern: &str) -> Self {
+        self.include.push(pattern.to_string());
+        self
+    }
+
+    /// Leaves out files matching any exclude pattern, even when they are included
+    pub fn with_exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(pattern.to_string());
+        self
+    }
+
+    /// Whether the file at `relative_path`, relative to the input folder, passes the patterns
+    pub fn is_selected(&self, relative_path: &Path) -> bool {
+        let path = relative_path.to_string_lossy().replace('\\', "/");
+        let file_name = path.rsplit('/').next().unwrap_or_default();
+        let matches = |pattern: &String| {
+            if pattern.contains('/') {
+                glob_match(pattern, &path)
+            } else {
+                glob_match(pattern, file_name)
+            }
+        };
+
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+}
+
+/// Matches `path` against a glob pattern with `*`, `?`, `**` and `{a,b}` alternatives
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let path: Vec<char> = path.chars().collect();
+
+    expand_alternatives(pattern).iter().any(|pattern| {
+        let pattern: Vec<char> = pattern.chars().collect();
+        match_from(&pattern, &path)
+    })
+}
+
+/// Expands the first `{a,b}` group of the pattern, and the groups after it recursively
+fn expand_alternatives(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let Some(close) = pattern[open..].find('}').map(|close| open + close) else {
+        return vec![pattern.to_string()];
+    };
+
+    pattern[open + 1..close]
+        .split(',')
+        .flat_map(|alternative| {
+            expand_alternatives(&format!(
+                "{}{}{}",
+                &pattern[..open],
+                alternative,
+                &pattern[close + 1..]
+            ))
+        })
+        .collect()
+}
+
+/// Matches the whole path, remembering the result for each pattern and path position,
+/// so runs of `*` and `**` do not try the same split again and again
+fn match_from(pattern: &[char], path: &[char]) -> bool {
+    let mut memo = vec![None; (pattern.len() + 1) * (path.len() + 1)];
+    match_at(pattern, path, 0, 0, &mut memo)
+}
+
+fn match_at(
+    pattern: &[char],
+    path: &[char],
+    pattern_index: usize,
+    path_index: usize,
+    memo: &mut [Option<bool>],
+) -> bool {
+    let key = pattern_index * (path.len() + 1) + path_index;
+    if let Some(matched) = memo[key] {
+        return matched;
+    }
+
+    let rest = &path[path_index..];
+    let matched = match pattern.get(pattern_index) {
+        None => rest.is_empty(),
+        Some('*') if pattern.get(pattern_index + 1) == Some(&'*') => {
+            let next = pattern_index + 2;
+            // `**/` also matches no folder at all
+            (pattern.get(next) == Some(&'/') && match_at(pattern, path, next + 1, path_index, memo))
+                || (0..=rest.len())
+                    .any(|skip| match_at(pattern, path, next, path_index + skip, memo))
+        }
+        Some('*') => {
+            let segment_length = rest.iter().position(|c| *c == '/').unwrap_or(rest.len());
+            (0..=segment_length)
+                .any(|skip| match_at(pattern, path, pattern_index + 1, path_index + skip, memo))
+        }
+        Some('?') => {
+            !rest.is_empty()
+                && rest[0] != '/'
+                && match_at(pattern, path, pattern_index + 1, path_index + 1, memo)
+        }
+        Some(c) => {
+            rest.first() == Some(c)
+                && match_at(pattern, path, pattern_index + 1, path_index + 1, memo)
+        }
+    };
+    memo[key] = Some(matched);
+
+    matched
+}
+
+/// Lists the image files in `dir` selected by the options, as paths relative to `dir`, sorted.
+/// Files without an image extension, and subfolders unless recursive, are skipped with a warning
+/// and returned next to the images.
+pub async fn find_images(
+    dir: &str,
+    options: &DirectoryOptions,
+) -> Result<(Vec<PathBuf>, Vec<SkippedFile>), Box<dyn std::error::Error>> {
+    let (images, skipped) = list_images(dir, options).await?;
+    for skipped_file in skipped.iter() {
+        match skipped_file.get_reason() {
+            "folder" => info!("Skipping folder {:?}", skipped_file.get_path()),
+            _ => warn!("Skipping {:?}, it is not an image", skipped_file.get_path()),
+        }
+    }
+
+    Ok((images, skipped))
+}
+
+/// Same as `find_images`, without logging the skipped files, for folders listed over and over
+pub async fn list_images(
+    dir: &str,
+    options: &DirectoryOptions,
+) -> Result<(Vec<PathBuf>, Vec<SkippedFile>), Box<dyn std::error::Error>> {
+    let mut images = Vec::new();
+    let mut skipped = Vec::new();
+    let mut pending_dirs = vec![PathBuf::new()];
+
+    while let Some(relative_dir) = pending_dirs.pop() {
+        let mut dir_entries = fs::read_dir(Path::new(dir).join(&relative_dir)).await?;
+        while let Some(entry) = dir_entries.next_entry().await? {
+            let relative_path = relative_dir.join(entry.file_name());
+            if entry.file_type().await?.is_dir() {
+                if options.recursive {
+                    pending_dirs.push(relative_path);
+                } else {
+                    skipped.push(SkippedFile::new(entry.path(), "folder"));
+                }
+                continue;
+            }
+            if !options.is_selected(&relative_path) {
+                continue;
+            }
+            if ImageFormat::from_path(&relative_path).is_err() {
+                skipped.push(SkippedFile::new(entry.path(), "not an image"));
+                continue;
+            }
+
+            images.push(relative_path);
+        }
+    }
+    images.sort();
+
+    Ok((images, skipped))
+}
@@ -1,81 +1,809 @@
-use image::{load_from_memory, GenericImageView, Pixel, Rgba};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use tokio::{fs::File, io::AsyncReadExt};
+use std::{
+    io::{BufReader, BufWriter, Cursor, Write},
+    path::Path,
+    time::Instant,
+};
 
-use crate::core::{image::Images, pixel::Pixels};
+use image::{
+    codecs::{
+        gif::GifDecoder,
+        jpeg::JpegEncoder,
+        png::{CompressionType, FilterType, PngDecoder, PngEncoder},
+        webp::WebPDecoder,
+    },
+    guess_format, load_from_memory, load_from_memory_with_format, AnimationDecoder, DynamicImage,
+    Frame, ImageFormat,
+};
+use log::{debug, info, warn};
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncReadExt},
+};
+
+use crate::{
+    core::{
+        animation::{Animation, AnimationFrame, Disposal},
+        image::Images,
+        operations::Operation,
+        sample::{Sample, SampleFormat},
+        tiling::{RowSink, RowSource},
+    },
+    transformations::rotate::orientation_operations,
+    utils::{
+        color_space_converter::{ConvertToWorkingSpace, IccProfile, WorkingSpace},
+        metadata::{embed_metadata, read_metadata, ImageMetadata},
+    },
+};
+
+/// Settings for reading images
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOptions {
+    auto_orient: bool,
+    working_space: Option<WorkingSpace>,
+}
+
+impl ReadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turns images upright according to their EXIF orientation, and resets the orientation tag
+    pub fn with_auto_orient(mut self, auto_orient: bool) -> Self {
+        self.auto_orient = auto_orient;
+        self
+    }
+
+    /// Converts images from their embedded ICC profile into the working space,
+    /// and replaces the profile with the one of the working space.
+    /// Images without a profile are taken as sRGB.
+    pub fn with_working_space(mut self, working_space: Option<WorkingSpace>) -> Self {
+        self.working_space = working_space;
+        self
+    }
+
+    /// Settings that change the decoded image, for the cache keys of `core::cache`
+    pub fn fingerprint(&self) -> String {
+        let working_space = match self.working_space {
+            Some(working_space) => format!("{:?}", working_space),
+            None => String::from("none"),
+        };
+
+        format!("read:{},{}", self.auto_orient, working_space)
+    }
+}
+
+/// Formats `process_images` and `image_writer_with_options` can write to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    WebP,
+    Bmp,
+    Tiff,
+    Pnm,
+    Gif,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::WebP => "webp",
+            OutputFormat::Bmp => "bmp",
+            OutputFormat::Tiff => "tiff",
+            OutputFormat::Pnm => "pnm",
+            OutputFormat::Gif => "gif",
+        }
+    }
+
+    /// Format of a file extension or format name, such as `jpg`, `jpeg` or `PNG`
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png),
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg),
+            "webp" => Some(OutputFormat::WebP),
+            "bmp" => Some(OutputFormat::Bmp),
+            "tif" | "tiff" => Some(OutputFormat::Tiff),
+            "pnm" | "pbm" | "pgm" | "ppm" => Some(OutputFormat::Pnm),
+            "gif" => Some(OutputFormat::Gif),
+            _ => None,
+        }
+    }
+
+    fn image_format(&self) -> ImageFormat {
+        match self {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Jpeg => ImageFormat::Jpeg,
+            OutputFormat::WebP => ImageFormat::WebP,
+            OutputFormat::Bmp => ImageFormat::Bmp,
+            OutputFormat::Tiff => ImageFormat::Tiff,
+            OutputFormat::Pnm => ImageFormat::Pnm,
+            OutputFormat::Gif => ImageFormat::Gif,
+        }
+    }
+}
+
+/// Output format and encoder settings.
+/// Without a format, the format follows the extension of the written file.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputOptions {
+    format: Option<OutputFormat>,
+    jpeg_quality: u8,
+    png_compression: CompressionType,
+    png_filter: FilterType,
+    keep_metadata: bool,
+    embed_icc_profile: bool,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            jpeg_quality: 75,
+            png_compression: CompressionType::Default,
+            png_filter: FilterType::Adaptive,
+            keep_metadata: false,
+            embed_icc_profile: true,
+        }
+    }
+}
+
+impl OutputOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// JPEG quality from 1 to 100, 75 by default
+    pub fn with_jpeg_quality(mut self, jpeg_quality: u8) -> Self {
+        self.jpeg_quality = jpeg_quality.clamp(1, 100);
+        self
+    }
+
+    pub fn with_png_compression(mut self, png_compression: CompressionType) -> Self {
+        self.png_compression = png_compression;
+        self
+    }
+
+    pub fn with_png_filter(mut self, png_filter: FilterType) -> Self {
+        self.png_filter = png_filter;
+        self
+    }
+
+    /// Writes the EXIF, ICC and XMP metadata of the source image to JPEG, PNG and WebP files.
+    /// Metadata is stripped by default, as EXIF data can hold locations and device details.
+    pub fn with_keep_metadata(mut self, keep_metadata: bool) -> Self {
+        self.keep_metadata = keep_metadata;
+        self
+    }
+
+    /// Writes the ICC profile of the source image even when other metadata is stripped,
+    /// so the colors are read back as they were processed. Enabled by default.
+    pub fn with_embed_icc_profile(mut self, embed_icc_profile: bool) -> Self {
+        self.embed_icc_profile = embed_icc_profile;
+        self
+    }
+
+    pub fn get_format(&self) -> Option<OutputFormat> {
+        self.format
+    }
+
+    /// Settings that change the written files, for the cache keys of `core::cache`
+    pub fn fingerprint(&self) -> String {
+        format!(
+            "output:{},{},{:?},{:?},{},{}",
+            self.format.map_or("source", |format| format.extension()),
+            self.jpeg_quality,
+            self.png_compression,
+            self.png_filter,
+            self.keep_metadata,
+            self.embed_icc_profile
+        )
+    }
+
+    /// Extension of the chosen format, or the extension of `source` to keep its format
+    pub fn extension_for(&self, source: &Path) -> String {
+        match self.format {
+            Some(format) => format.extension().to_string(),
+            None => source
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .unwrap_or(OutputFormat::Png.extension())
+                .to_string(),
+        }
+    }
+}
+
+/// Converts the bit depth of `image` while keeping its channel layout
+fn convert_depth(image: &DynamicImage, channels: u8, depth: SampleFormat) -> DynamicImage {
+    match (depth, channels) {
+        (SampleFormat::U8, 1) => DynamicImage::ImageLuma8(image.to_luma8()),
+        (SampleFormat::U8, 2) => DynamicImage::ImageLumaA8(image.to_luma_alpha8()),
+        (SampleFormat::U8, 3) => DynamicImage::ImageRgb8(image.to_rgb8()),
+        (SampleFormat::U8, _) => DynamicImage::ImageRgba8(image.to_rgba8()),
+        (SampleFormat::U16, 1) => DynamicImage::ImageLuma16(image.to_luma16()),
+        (SampleFormat::U16, 2) => DynamicImage::ImageLumaA16(image.to_luma_alpha16()),
+        (SampleFormat::U16, 3) => DynamicImage::ImageRgb16(image.to_rgb16()),
+        (SampleFormat::U16, _) => DynamicImage::ImageRgba16(image.to_rgba16()),
+        (SampleFormat::F32, 1) | (SampleFormat::F32, 3) => {
+            DynamicImage::ImageRgb32F(image.to_rgb32f())
+        }
+        (SampleFormat::F32, _) => DynamicImage::ImageRgba32F(image.to_rgba32f()),
+    }
+}
 
 pub async fn image_reader<T>(filepath: &str) -> Result<Images<T>, Box<dyn std::error::Error>>
 where
-    T: Copy + Clone + From<u8> + Into<u8> + std::cmp::PartialEq + Send + Sync,
+    T: Sample,
+{
+    let (image, _) = image_reader_with_options(filepath, &ReadOptions::default()).await?;
+
+    Ok(image)
+}
+
+/// Reads the image together with its EXIF, ICC and XMP metadata
+pub async fn image_reader_with_options<T>(
+    filepath: &str,
+    read_options: &ReadOptions,
+) -> Result<(Images<T>, ImageMetadata), Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let started = Instant::now();
+    let file = File::open(filepath).await?;
+    debug!("Reading image {:?}", filepath);
+    let read = async_image_reader(file, None, read_options).await?;
+    info!("Read image {:?} in {:?}", filepath, started.elapsed());
+
+    Ok(read)
+}
+
+/// Reads the image from any async reader, such as a socket or a request body.
+/// Without a format, the format is sniffed from the magic bytes.
+pub async fn async_image_reader<T, R>(
+    mut reader: R,
+    format: Option<ImageFormat>,
+    read_options: &ReadOptions,
+) -> Result<(Images<T>, ImageMetadata), Box<dyn std::error::Error>>
+where
+    T: Sample,
+    R: AsyncRead + Unpin,
 {
-    let mut file = File::open(filepath).await?;
-    println!("INFO: Starting to read image from {:?} . . .", filepath);
     let mut tokio_image_bytes: Vec<u8> = Vec::new();
-    file.read_to_end(&mut tokio_image_bytes).await?; // Reads all bytes until EOF, and places them into buf.
-    let read_image = load_from_memory(&tokio_image_bytes)?;
-
-    let mut image_bytes: Vec<Pixels<T>> = Vec::new();
-
-    let read_pixels = (0..read_image.height())
-        .into_par_iter()
-        .flat_map(|h_index| {
-            (0..read_image.width())
-                .into_par_iter()
-                .map(|w_index| {
-                    let pixel = read_image.get_pixel(w_index, h_index).to_rgba();
-                    Pixels::new(
-                        (*pixel.channels().first().unwrap()).into(),
-                        (*pixel.channels().get(1).unwrap()).into(),
-                        (*pixel.channels().get(2).unwrap()).into(),
-                        (*pixel.channels().get(3).unwrap()).into(),
-                    )
-                })
-                .collect::<Vec<Pixels<T>>>()
-        })
-        .collect::<Vec<Pixels<T>>>();
+    reader.read_to_end(&mut tokio_image_bytes).await?; // Reads all bytes until EOF, and places them into buf.
 
-    for pix in read_pixels.iter() {
-        image_bytes.push(pix.clone());
-    }
+    decode_image(&tokio_image_bytes, format, read_options)
+}
 
-    let image = Images::new(
-        read_image.width(),
-        read_image.height(),
-        read_image.color().channel_count(),
-        image_bytes,
-    );
-    println!("INFO: Finished reading image from {:?}", filepath);
+/// Reads the image piped into the standard input
+pub async fn stdin_image_reader<T>(
+    format: Option<ImageFormat>,
+    read_options: &ReadOptions,
+) -> Result<(Images<T>, ImageMetadata), Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    async_image_reader(tokio::io::stdin(), format, read_options).await
+}
 
-    Ok(image)
+/// Decodes an encoded image held in memory.
+/// Without a format, the format is sniffed from the magic bytes.
+pub fn decode_image<T>(
+    bytes: &[u8],
+    format: Option<ImageFormat>,
+    read_options: &ReadOptions,
+) -> Result<(Images<T>, ImageMetadata), Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let read_image = match format {
+        Some(format) => load_from_memory_with_format(bytes, format)?,
+        None => load_from_memory(bytes)?,
+    };
+    let mut metadata = read_metadata(bytes);
+
+    let mut image = Images::from(read_image);
+    if read_options.auto_orient {
+        if let Some(orientation) = metadata.get_orientation() {
+            for operation in orientation_operations::<T>(orientation) {
+                image = operation.apply(&image);
+            }
+            metadata.reset_orientation();
+        }
+    }
+    if let Some(working_space) = read_options.working_space {
+        let source = match metadata.get_icc_profile() {
+            Some(icc_profile) => IccProfile::parse(icc_profile),
+            None => Ok(IccProfile::srgb()),
+        };
+        match source {
+            Ok(source) => {
+                image = ConvertToWorkingSpace::new(source, working_space).apply(&image);
+                metadata.set_icc_profile(Some(working_space.profile().to_bytes()));
+            }
+            Err(error) => warn!("Keeping the colors of the image unconverted: {}", error),
+        }
+    }
+
+    Ok((image, metadata))
 }
 
+/// Writes the image with the format taken from the file extension and default encoder settings
 pub fn image_writer<T>(
     filepath: &str,
     write_image: &Images<T>,
 ) -> Result<(), Box<dyn std::error::Error>>
 where
-    T: Copy + Clone + From<u8> + Into<u8> + std::cmp::PartialEq + Send + Sync,
-{
-    let mut image: image::DynamicImage =
-        image::DynamicImage::new_rgba8(write_image.get_width(), write_image.get_height());
-
-    println!("INFO: Starting to write image to {:?} . . .", filepath);
-    let rgba_image = image.as_mut_rgba8().unwrap();
-    rgba_image
-        .enumerate_pixels_mut()
-        .for_each(|(x_index, y_index, pixel)| {
-            let rgba = &write_image;
-            let pixel_data = rgba.get_pixel_at(x_index, y_index).unwrap();
-            *pixel = Rgba([
-                pixel_data.get_red().into(),
-                pixel_data.get_green().into(),
-                pixel_data.get_blue().into(),
-                pixel_data.get_alpha().into(),
-            ]);
-        });
+    T: Sample,
+{
+    image_writer_with_options(
+        filepath,
+        write_image,
+        &OutputOptions::default(),
+        &ImageMetadata::default(),
+    )
+}
 
-    image.save(filepath)?;
-    println!("INFO: Saved image to {:?}", filepath);
+/// Writes the image with the format taken from the options or the file extension.
+/// See `encode_image` for the channel layout, bit depth and metadata written.
+pub fn image_writer_with_options<T>(
+    filepath: &str,
+    write_image: &Images<T>,
+    output_options: &OutputOptions,
+    metadata: &ImageMetadata,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let started = Instant::now();
+    debug!("Writing image {:?}", filepath);
+    let format = match output_options.format {
+        Some(format) => format.image_format(),
+        None => ImageFormat::from_path(Path::new(filepath))?,
+    };
+    let encoded = encode_with_format(write_image, format, output_options, metadata)?;
+    std::fs::write(filepath, encoded)?;
+    info!("Saved image {:?} in {:?}", filepath, started.elapsed());
 
     Ok(())
 }
+
+/// Encodes the image into the writer, such as a response body or the standard output.
+/// The options need a format, as there is no file extension to take it from.
+pub fn image_to_writer<T, W>(
+    writer: &mut W,
+    write_image: &Images<T>,
+    output_options: &OutputOptions,
+    metadata: &ImageMetadata,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Sample,
+    W: Write,
+{
+    writer.write_all(&encode_image(write_image, output_options, metadata)?)?;
+
+    Ok(())
+}
+
+/// Encodes the image in memory, in the format of the options.
+/// The image keeps its own channel layout (gray, gray + alpha, RGB or RGBA),
+/// and the bit depth of `T` when the output format supports it:
+/// 16 bit for PNG, TIFF and PNM, 32 bit float for OpenEXR, and 8 bit otherwise.
+/// JPEG has no alpha channel, so alpha is dropped, and GIF has no gray, so gray is written as RGB.
+/// `metadata` is only written when the options keep metadata, apart from its ICC profile,
+/// which is written unless the options disable it.
+pub fn encode_image<T>(
+    write_image: &Images<T>,
+    output_options: &OutputOptions,
+    metadata: &ImageMetadata,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let Some(format) = output_options.format else {
+        return Err("An output format is needed to encode an image in memory".into());
+    };
+
+    encode_with_format(write_image, format.image_format(), output_options, metadata)
+}
+
+fn encode_with_format<T>(
+    write_image: &Images<T>,
+    format: ImageFormat,
+    output_options: &OutputOptions,
+    metadata: &ImageMetadata,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let channels = write_image.get_channels();
+    let image = DynamicImage::try_from(write_image.clone())?;
+
+    let depth = match format {
+        ImageFormat::OpenExr => SampleFormat::F32,
+        ImageFormat::Png | ImageFormat::Tiff | ImageFormat::Pnm
+            if T::FORMAT != SampleFormat::U8 =>
+        {
+            SampleFormat::U16
+        }
+        _ => SampleFormat::U8,
+    };
+    let target_channels = match (format, channels) {
+        (ImageFormat::Jpeg, 1 | 2) => 1,
+        (ImageFormat::Jpeg, _) => 3,
+        (ImageFormat::Gif, 1 | 3) => 3,
+        (ImageFormat::Gif, _) => 4,
+        _ => channels,
+    };
+    let image = if depth == T::FORMAT && target_channels == channels {
+        image
+    } else {
+        convert_depth(&image, target_channels, depth)
+    };
+
+    let mut writer = Cursor::new(Vec::new());
+    match format {
+        ImageFormat::Png => image.write_with_encoder(PngEncoder::new_with_quality(
+            &mut writer,
+            output_options.png_compression,
+            output_options.png_filter,
+        ))?,
+        ImageFormat::Jpeg => image.write_with_encoder(JpegEncoder::new_with_quality(
+            &mut writer,
+            output_options.jpeg_quality,
+        ))?,
+        _ => image.write_to(&mut writer, format)?,
+    }
+    let mut encoded = writer.into_inner();
+    if output_options.keep_metadata {
+        encoded = embed_metadata(encoded, format, metadata)?;
+    } else if output_options.embed_icc_profile {
+        encoded = embed_metadata(encoded, format, &metadata.icc_profile_only())?;
+    }
+
+    Ok(encoded)
+}
+
+/// Reads every frame of an animated GIF, APNG or WebP image.
+/// Still images are read as an animation with a single frame.
+/// The decoders do not expose the loop count, so read animations loop forever.
+pub async fn animation_reader<T>(filepath: &str) -> Result<Animation<T>, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let mut file = File::open(filepath).await?;
+    let started = Instant::now();
+    debug!("Reading animation {:?}", filepath);
+    let mut tokio_image_bytes: Vec<u8> = Vec::new();
+    file.read_to_end(&mut tokio_image_bytes).await?;
+
+    let decoded_frames: Vec<Frame> = match guess_format(&tokio_image_bytes)? {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(&tokio_image_bytes))?
+            .into_frames()
+            .collect_frames()?,
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(Cursor::new(&tokio_image_bytes))?;
+            if decoder.is_apng()? {
+                decoder.apng()?.into_frames().collect_frames()?
+            } else {
+                Vec::new()
+            }
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(Cursor::new(&tokio_image_bytes))?;
+            if decoder.has_animation() {
+                decoder.into_frames().collect_frames()?
+            } else {
+                Vec::new()
+            }
+        }
+        _ => Vec::new(),
+    };
+
+    let frames = if decoded_frames.is_empty() {
+        let image = Images::from(load_from_memory(&tokio_image_bytes)?);
+        vec![AnimationFrame::new(image, 0, Disposal::None)]
+    } else {
+        decoded_frames
+            .into_iter()
+            .map(|frame| {
+                let (numerator, denominator) = frame.delay().numer_denom_ms();
+                let delay_ms = numerator.checked_div(denominator).unwrap_or(0);
+                let image = Images::from(DynamicImage::ImageRgba8(frame.into_buffer()));
+                AnimationFrame::new(image, delay_ms, Disposal::Background)
+            })
+            .collect()
+    };
+    info!(
+        "Read {} frame(s) from {:?} in {:?}",
+        frames.len(),
+        filepath,
+        started.elapsed()
+    );
+
+    Ok(Animation::new(frames, 0))
+}
+
+/// Writes the animation as an animated GIF or APNG, with the format taken from the options
+/// or the file extension. Other formats can only be written from a single frame.
+/// GIF delays are stored in hundredths of a second, so they are rounded down to 10 ms steps.
+pub fn animation_writer<T>(
+    filepath: &str,
+    animation: &Animation<T>,
+    output_options: &OutputOptions,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let format = match output_options.format {
+        Some(format) => format.image_format(),
+        None => ImageFormat::from_path(Path::new(filepath))?,
+    };
+    let frames = animation.get_frames();
+    let Some((width, height)) = animation.get_dimensions() else {
+        return Err("An animation needs at least one frame".into());
+    };
+    if frames.iter().any(|frame| {
+        frame.get_image().get_width() != width || frame.get_image().get_height() != height
+    }) {
+        return Err("All frames of an animation need the same size".into());
+    }
+
+    let encoded = match format {
+        ImageFormat::Gif => encode_gif(animation, width, height)?,
+        ImageFormat::Png => encode_apng(animation, width, height)?,
+        _ if frames.len() == 1 => {
+            return image_writer_with_options(
+                filepath,
+                frames[0].get_image(),
+                output_options,
+                &ImageMetadata::default(),
+            );
+        }
+        _ => return Err(format!("Writing animations to {:?} is not supported", format).into()),
+    };
+    std::fs::write(filepath, encoded)?;
+    info!("Saved {} frame(s) to {:?}", frames.len(), filepath);
+
+    Ok(())
+}
+
+fn encode_gif<T>(
+    animation: &Animation<T>,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let width = u16::try_from(width)?;
+    let height = u16::try_from(height)?;
+    let mut encoder = gif::Encoder::new(Vec::new(), width, height, &[])?;
+    // The GIF loop count is the number of repetitions after the first play
+    match animation.get_loop_count() {
+        0 => encoder.set_repeat(gif::Repeat::Infinite)?,
+        1 => {}
+        loop_count => encoder.set_repeat(gif::Repeat::Finite(
+            u16::try_from(loop_count - 1).unwrap_or(u16::MAX),
+        ))?,
+    }
+
+    for frame in animation.get_frames() {
+        let mut pixels = DynamicImage::try_from(frame.get_image().clone())?
+            .to_rgba8()
+            .into_raw();
+        let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+        gif_frame.delay = u16::try_from(frame.get_delay_ms() / 10).unwrap_or(u16::MAX);
+        gif_frame.dispose = match frame.get_disposal() {
+            Disposal::None => gif::DisposalMethod::Keep,
+            Disposal::Background => gif::DisposalMethod::Background,
+            Disposal::Previous => gif::DisposalMethod::Previous,
+        };
+        encoder.write_frame(&gif_frame)?;
+    }
+
+    Ok(encoder.into_inner()?)
+}
+
+/// Encodes RGBA frames, with 16 bit samples when `T` is wider than 8 bit
+fn encode_apng<T>(
+    animation: &Animation<T>,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let frames = animation.get_frames();
+    let sixteen_bit = T::FORMAT != SampleFormat::U8;
+    let mut encoded = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut encoded, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(if sixteen_bit {
+        png::BitDepth::Sixteen
+    } else {
+        png::BitDepth::Eight
+    });
+    encoder.set_animated(frames.len() as u32, animation.get_loop_count())?;
+    let mut writer = encoder.write_header()?;
+
+    for frame in frames {
+        let image = DynamicImage::try_from(frame.get_image().clone())?;
+        let data: Vec<u8> = if sixteen_bit {
+            image
+                .to_rgba16()
+                .into_raw()
+                .iter()
+                .flat_map(|sample| sample.to_be_bytes())
+                .collect()
+        } else {
+            image.to_rgba8().into_raw()
+        };
+        writer.set_frame_delay(
+            u16::try_from(frame.get_delay_ms()).unwrap_or(u16::MAX),
+            1000,
+        )?;
+        writer.set_dispose_op(match frame.get_disposal() {
+            Disposal::None => png::DisposeOp::None,
+            Disposal::Background => png::DisposeOp::Background,
+            Disposal::Previous => png::DisposeOp::Previous,
+        })?;
+        writer.set_blend_op(png::BlendOp::Source)?;
+        writer.write_image_data(&data)?;
+    }
+    writer.finish()?;
+
+    Ok(encoded)
+}
+
+/// Reads a PNG file row by row for `core::tiling`, without decoding the whole image.
+/// Palette and low bit depth images are expanded to 8 bit, interlaced images are not supported.
+pub struct PngRowReader {
+    reader: png::Reader<BufReader<std::fs::File>>,
+    channels: u8,
+    sixteen_bit: bool,
+}
+
+impl PngRowReader {
+    pub fn open(filepath: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut decoder = png::Decoder::new(BufReader::new(std::fs::File::open(filepath)?));
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let reader = decoder.read_info()?;
+        if reader.info().interlaced {
+            return Err(format!("Interlaced PNG {:?} can not be read row by row", filepath).into());
+        }
+
+        let (color_type, bit_depth) = reader.output_color_type();
+        let channels = match color_type {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            png::ColorType::Rgb => 3,
+            _ => 4,
+        };
+
+        Ok(Self {
+            reader,
+            channels,
+            sixteen_bit: bit_depth == png::BitDepth::Sixteen,
+        })
+    }
+}
+
+impl<T> RowSource<T> for PngRowReader
+where
+    T: Sample,
+{
+    fn get_dimensions(&self) -> (u32, u32) {
+        let info = self.reader.info();
+        (info.width, info.height)
+    }
+
+    fn get_channels(&self) -> u8 {
+        self.channels
+    }
+
+    fn read_rows(&mut self, rows: u32) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        let mut samples = Vec::new();
+        for _ in 0..rows {
+            let Some(row) = self.reader.next_row()? else {
+                return Err("Reading rows past the end of the image".into());
+            };
+            if self.sixteen_bit {
+                samples.extend(row.data().chunks_exact(2).map(|bytes| {
+                    T::from_unit(u16::from_be_bytes([bytes[0], bytes[1]]) as f64 / 65535.0)
+                }));
+            } else {
+                samples.extend(
+                    row.data()
+                        .iter()
+                        .map(|value| T::from_unit(*value as f64 / 255.0)),
+                );
+            }
+        }
+
+        Ok(samples)
+    }
+}
+
+/// Writes a PNG file band by band for `core::tiling`, compressing rows as they arrive.
+/// Samples are written with 8 bit for `u8` images and 16 bit otherwise.
+pub struct PngRowWriter {
+    filepath: String,
+    writer: Option<png::StreamWriter<'static, BufWriter<std::fs::File>>>,
+}
+
+impl PngRowWriter {
+    pub fn new(filepath: &str) -> Self {
+        Self {
+            filepath: filepath.to_string(),
+            writer: None,
+        }
+    }
+}
+
+impl<T> RowSink<T> for PngRowWriter
+where
+    T: Sample,
+{
+    fn start(
+        &mut self,
+        width: u32,
+        height: u32,
+        channels: u8,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        debug!("Writing image {:?} row by row", self.filepath);
+        let file = BufWriter::new(std::fs::File::create(&self.filepath)?);
+        let mut encoder = png::Encoder::new(file, width, height);
+        encoder.set_color(match channels {
+            1 => png::ColorType::Grayscale,
+            2 => png::ColorType::GrayscaleAlpha,
+            3 => png::ColorType::Rgb,
+            _ => png::ColorType::Rgba,
+        });
+        encoder.set_depth(if T::FORMAT == SampleFormat::U8 {
+            png::BitDepth::Eight
+        } else {
+            png::BitDepth::Sixteen
+        });
+        self.writer = Some(encoder.write_header()?.into_stream_writer()?);
+
+        Ok(())
+    }
+
+    fn write_rows(&mut self, rows: &Images<T>) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(writer) = self.writer.as_mut() else {
+            return Err("Rows written before the PNG was started".into());
+        };
+        let data: Vec<u8> = if T::FORMAT == SampleFormat::U8 {
+            rows.get_samples()
+                .iter()
+                .map(|sample| (sample.to_unit() * 255.0).round().clamp(0.0, 255.0) as u8)
+                .collect()
+        } else {
+            rows.get_samples()
+                .iter()
+                .flat_map(|sample| {
+                    ((sample.to_unit() * 65535.0).round().clamp(0.0, 65535.0) as u16).to_be_bytes()
+                })
+                .collect()
+        };
+        writer.write_all(&data)?;
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(writer) = self.writer.take() {
+            writer.finish()?;
+            info!("Saved image {:?}", self.filepath);
+        }
+
+        Ok(())
+    }
+}
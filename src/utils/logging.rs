@@ -0,0 +1,26 @@
+/// Prints the log events of the library from the info level up
+pub struct StdoutLogger;
+
+impl log::Log for StdoutLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            println!("{}: {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+pub static LOGGER: StdoutLogger = StdoutLogger;
+
+/// Installs `LOGGER` as the logger of the program, which can only be done once
+pub fn init_stdout_logger() -> Result<(), Box<dyn std::error::Error>> {
+    log::set_logger(&LOGGER).map_err(|error| error.to_string())?;
+    log::set_max_level(log::LevelFilter::Info);
+
+    Ok(())
+}
@@ -0,0 +1,482 @@
+use std::io::{Read, Write};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use image::ImageFormat;
+use log::warn;
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const JPEG_ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+// A JPEG segment holds at most 65535 bytes, including its 2 length bytes
+const JPEG_SEGMENT_CAPACITY: usize = 65533;
+const PNG_SIGNATURE: &[u8] = &[137, 80, 78, 71, 13, 10, 26, 10];
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+const EXIF_ORIENTATION_TAG: u16 = 0x0112;
+
+/// EXIF, ICC profile and XMP metadata of an encoded image.
+/// EXIF data is kept in TIFF layout, without the `Exif\0\0` header JPEG files add.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageMetadata {
+    exif: Option<Vec<u8>>,
+    icc_profile: Option<Vec<u8>>,
+    xmp: Option<Vec<u8>>,
+}
+
+impl ImageMetadata {
+    pub fn new(exif: Option<Vec<u8>>, icc_profile: Option<Vec<u8>>, xmp: Option<Vec<u8>>) -> Self {
+        Self {
+            exif,
+            icc_profile,
+            xmp,
+        }
+    }
+
+    pub fn get_exif(&self) -> Option<&[u8]> {
+        self.exif.as_deref()
+    }
+
+    pub fn get_icc_profile(&self) -> Option<&[u8]> {
+        self.icc_profile.as_deref()
+    }
+
+    pub fn get_xmp(&self) -> Option<&[u8]> {
+        self.xmp.as_deref()
+    }
+
+    pub fn set_icc_profile(&mut self, icc_profile: Option<Vec<u8>>) {
+        self.icc_profile = icc_profile;
+    }
+
+    /// Only the ICC profile, which describes colors and carries nothing private
+    pub fn icc_profile_only(&self) -> Self {
+        Self::new(None, self.icc_profile.clone(), None)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exif.is_none() && self.icc_profile.is_none() && self.xmp.is_none()
+    }
+
+    /// EXIF orientation from 1 to 8, see `orientation_operations`
+    pub fn get_orientation(&self) -> Option<u16> {
+        self.exif.as_deref().and_then(exif_orientation)
+    }
+
+    /// Marks the image as upright, once the orientation has been applied to the pixels,
+    /// so viewers do not rotate it a second time
+    pub fn reset_orientation(&mut self) {
+        if let Some(offset) = self.exif.as_deref().and_then(orientation_value_offset) {
+            let exif = self.exif.as_mut().unwrap();
+            let value: [u8; 2] = if exif.starts_with(b"II") {
+                1_u16.to_le_bytes()
+            } else {
+                1_u16.to_be_bytes()
+            };
+            exif[offset..offset + 2].copy_from_slice(&value);
+        }
+    }
+}
+
+fn strip_exif_header(exif: &[u8]) -> Vec<u8> {
+    exif.strip_prefix(EXIF_HEADER).unwrap_or(exif).to_vec()
+}
+
+fn read_u16(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u16> {
+    let value: [u8; 2] = bytes.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if little_endian {
+        u16::from_le_bytes(value)
+    } else {
+        u16::from_be_bytes(value)
+    })
+}
+
+fn read_u32(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u32> {
+    let value: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if little_endian {
+        u32::from_le_bytes(value)
+    } else {
+        u32::from_be_bytes(value)
+    })
+}
+
+/// Offset of the orientation value in the first IFD of EXIF data in TIFF layout
+fn orientation_value_offset(exif: &[u8]) -> Option<usize> {
+    let little_endian = match exif.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let ifd_offset = read_u32(exif, 4, little_endian)? as usize;
+    let entry_count = read_u16(exif, ifd_offset, little_endian)? as usize;
+
+    (0..entry_count)
+        .map(|index| ifd_offset + 2 + index * 12)
+        .find(|entry| read_u16(exif, *entry, little_endian) == Some(EXIF_ORIENTATION_TAG))
+        .map(|entry| entry + 8)
+        .filter(|offset| exif.len() >= offset + 2)
+}
+
+/// Orientation tag of EXIF data in TIFF layout, `None` when missing or outside 1 to 8
+pub fn exif_orientation(exif: &[u8]) -> Option<u16> {
+    let offset = orientation_value_offset(exif)?;
+    let orientation = read_u16(exif, offset, exif.starts_with(b"II"))?;
+
+    (1..=8).contains(&orientation).then_some(orientation)
+}
+
+/// Marker and payload of every JPEG segment before the image data
+fn jpeg_segments(bytes: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut segments = Vec::new();
+    let mut position = 2;
+    while position + 4 <= bytes.len() && bytes[position] == 0xFF {
+        let marker = bytes[position + 1];
+        match marker {
+            // Fill bytes, and markers without a payload
+            0xFF => position += 1,
+            0x01 | 0xD0..=0xD8 => position += 2,
+            // Start of scan and end of image
+            0xDA | 0xD9 => break,
+            _ => {
+                let length =
+                    u16::from_be_bytes([bytes[position + 2], bytes[position + 3]]) as usize;
+                let Some(payload) = bytes.get(position + 4..position + 2 + length) else {
+                    break;
+                };
+                segments.push((marker, payload));
+                position += 2 + length;
+            }
+        }
+    }
+
+    segments
+}
+
+fn read_jpeg_metadata(bytes: &[u8]) -> ImageMetadata {
+    let mut metadata = ImageMetadata::default();
+    let mut icc_chunks: Vec<(u8, &[u8])> = Vec::new();
+    for (marker, payload) in jpeg_segments(bytes) {
+        if marker == 0xE1 && payload.starts_with(EXIF_HEADER) {
+            metadata.exif = Some(strip_exif_header(payload));
+        } else if marker == 0xE1 && payload.starts_with(JPEG_XMP_HEADER) {
+            metadata.xmp = Some(payload[JPEG_XMP_HEADER.len()..].to_vec());
+        } else if marker == 0xE2 && payload.starts_with(JPEG_ICC_HEADER) {
+            // Profiles are split over several segments, each with its sequence number
+            let header_len = JPEG_ICC_HEADER.len();
+            if let Some(sequence) = payload.get(header_len) {
+                icc_chunks.push((*sequence, payload.get(header_len + 2..).unwrap_or_default()));
+            }
+        }
+    }
+    if !icc_chunks.is_empty() {
+        icc_chunks.sort_by_key(|(sequence, _)| *sequence);
+        metadata.icc_profile = Some(
+            icc_chunks
+                .into_iter()
+                .flat_map(|(_, chunk)| chunk.to_vec())
+                .collect(),
+        );
+    }
+
+    metadata
+}
+
+/// Type and data of every PNG chunk
+fn png_chunks(bytes: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut chunks = Vec::new();
+    let mut position = PNG_SIGNATURE.len();
+    while position + 12 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[position..position + 4].try_into().unwrap()) as usize;
+        let chunk_type = &bytes[position + 4..position + 8];
+        let Some(data) = bytes.get(position + 8..position + 8 + length) else {
+            break;
+        };
+        chunks.push((chunk_type, data));
+        position += 12 + length;
+    }
+
+    chunks
+}
+
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(data).read_to_end(&mut inflated).ok()?;
+    Some(inflated)
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Text of an `iTXt` chunk with the given keyword
+fn png_international_text(data: &[u8], keyword: &[u8]) -> Option<Vec<u8>> {
+    let rest = data.strip_prefix(keyword)?.strip_prefix(&[0])?;
+    let (compressed, rest) = (*rest.first()? == 1, rest.get(2..)?);
+    // Skip the language tag and the translated keyword
+    let language_end = rest.iter().position(|byte| *byte == 0)?;
+    let rest = &rest[language_end + 1..];
+    let translated_end = rest.iter().position(|byte| *byte == 0)?;
+    let text = &rest[translated_end + 1..];
+
+    if compressed {
+        inflate(text)
+    } else {
+        Some(text.to_vec())
+    }
+}
+
+fn read_png_metadata(bytes: &[u8]) -> ImageMetadata {
+    let mut metadata = ImageMetadata::default();
+    for (chunk_type, data) in png_chunks(bytes) {
+        match chunk_type {
+            b"eXIf" => metadata.exif = Some(strip_exif_header(data)),
+            b"iCCP" => {
+                // Profile name, compression method, then the zlib compressed profile
+                metadata.icc_profile = data
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .and_then(|name_end| data.get(name_end + 2..))
+                    .and_then(inflate);
+            }
+            b"iTXt" => {
+                if let Some(xmp) = png_international_text(data, PNG_XMP_KEYWORD) {
+                    metadata.xmp = Some(xmp);
+                }
+            }
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    metadata
+}
+
+/// FourCC and data of every chunk in a RIFF WebP file
+fn webp_chunks(bytes: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut chunks = Vec::new();
+    let mut position = 12;
+    while position + 8 <= bytes.len() {
+        let fourcc = &bytes[position..position + 4];
+        let length =
+            u32::from_le_bytes(bytes[position + 4..position + 8].try_into().unwrap()) as usize;
+        let Some(data) = bytes.get(position + 8..position + 8 + length) else {
+            break;
+        };
+        chunks.push((fourcc, data));
+        // Chunks are padded to an even size
+        position += 8 + length + length % 2;
+    }
+
+    chunks
+}
+
+fn read_webp_metadata(bytes: &[u8]) -> ImageMetadata {
+    let mut metadata = ImageMetadata::default();
+    for (fourcc, data) in webp_chunks(bytes) {
+        match fourcc {
+            b"EXIF" => metadata.exif = Some(strip_exif_header(data)),
+            b"ICCP" => metadata.icc_profile = Some(data.to_vec()),
+            b"XMP " => metadata.xmp = Some(data.to_vec()),
+            _ => {}
+        }
+    }
+
+    metadata
+}
+
+/// Reads the metadata of an encoded JPEG, PNG or WebP image.
+/// Other formats give empty metadata.
+pub fn read_metadata(bytes: &[u8]) -> ImageMetadata {
+    match image::guess_format(bytes) {
+        Ok(ImageFormat::Jpeg) => read_jpeg_metadata(bytes),
+        Ok(ImageFormat::Png) => read_png_metadata(bytes),
+        Ok(ImageFormat::WebP) => read_webp_metadata(bytes),
+        _ => ImageMetadata::default(),
+    }
+}
+
+fn jpeg_segment(
+    marker: u8,
+    header: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let length = header.len() + data.len() + 2;
+    if length > JPEG_SEGMENT_CAPACITY + 2 {
+        return Err("Metadata too large for a JPEG segment".into());
+    }
+    let mut segment = vec![0xFF, marker];
+    segment.extend((length as u16).to_be_bytes());
+    segment.extend(header);
+    segment.extend(data);
+
+    Ok(segment)
+}
+
+fn embed_jpeg_metadata(
+    encoded: &[u8],
+    metadata: &ImageMetadata,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut segments = Vec::new();
+    if let Some(exif) = &metadata.exif {
+        segments.extend(jpeg_segment(0xE1, EXIF_HEADER, exif)?);
+    }
+    if let Some(xmp) = &metadata.xmp {
+        segments.extend(jpeg_segment(0xE1, JPEG_XMP_HEADER, xmp)?);
+    }
+    if let Some(icc_profile) = &metadata.icc_profile {
+        let chunk_capacity = JPEG_SEGMENT_CAPACITY - JPEG_ICC_HEADER.len() - 2;
+        let chunks: Vec<&[u8]> = icc_profile.chunks(chunk_capacity).collect();
+        if chunks.len() > u8::MAX as usize {
+            return Err("ICC profile too large for a JPEG file".into());
+        }
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut header = JPEG_ICC_HEADER.to_vec();
+            header.extend([index as u8 + 1, chunks.len() as u8]);
+            segments.extend(jpeg_segment(0xE2, &header, chunk)?);
+        }
+    }
+
+    // Metadata goes after the start of image marker and the JFIF segment
+    let mut insert_at = 2;
+    if let Some((0xE0, payload)) = jpeg_segments(encoded).first() {
+        insert_at += 4 + payload.len();
+    }
+    let mut embedded = encoded[..insert_at].to_vec();
+    embedded.extend(segments);
+    embedded.extend(&encoded[insert_at..]);
+
+    Ok(embedded)
+}
+
+fn png_chunk(chunk_type: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+    chunk.extend(chunk_type);
+    chunk.extend(data);
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(chunk_type);
+    hasher.update(data);
+    chunk.extend(hasher.finalize().to_be_bytes());
+
+    chunk
+}
+
+fn embed_png_metadata(
+    encoded: &[u8],
+    metadata: &ImageMetadata,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut chunks = Vec::new();
+    if let Some(icc_profile) = &metadata.icc_profile {
+        let mut data = b"icc\0\0".to_vec();
+        data.extend(deflate(icc_profile)?);
+        chunks.extend(png_chunk(b"iCCP", &data));
+    }
+    if let Some(exif) = &metadata.exif {
+        chunks.extend(png_chunk(b"eXIf", exif));
+    }
+    if let Some(xmp) = &metadata.xmp {
+        // Uncompressed, without language tag or translated keyword
+        let mut data = PNG_XMP_KEYWORD.to_vec();
+        data.extend([0, 0, 0, 0, 0]);
+        data.extend(xmp);
+        chunks.extend(png_chunk(b"iTXt", &data));
+    }
+
+    // Metadata goes right after the IHDR chunk, before any image data
+    let ihdr_length = png_chunks(encoded)
+        .first()
+        .filter(|(chunk_type, _)| *chunk_type == b"IHDR")
+        .map(|(_, data)| data.len())
+        .ok_or("PNG data does not start with an IHDR chunk")?;
+    let insert_at = PNG_SIGNATURE.len() + 12 + ihdr_length;
+    let mut embedded = encoded[..insert_at].to_vec();
+    embedded.extend(chunks);
+    embedded.extend(&encoded[insert_at..]);
+
+    Ok(embedded)
+}
+
+fn webp_chunk(fourcc: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut chunk = fourcc.to_vec();
+    chunk.extend((data.len() as u32).to_le_bytes());
+    chunk.extend(data);
+    if data.len() % 2 == 1 {
+        chunk.push(0);
+    }
+
+    chunk
+}
+
+/// Rewrites a simple lossless WebP file in the extended format, which can hold metadata
+fn embed_webp_metadata(
+    encoded: &[u8],
+    metadata: &ImageMetadata,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let chunks = webp_chunks(encoded);
+    let Some((b"VP8L", bitstream)) = chunks.first().map(|(fourcc, data)| (*fourcc, *data)) else {
+        return Err("Metadata can only be added to simple lossless WebP files".into());
+    };
+    // Lossless header: signature byte, then 14 bits width - 1, 14 bits height - 1 and the alpha bit
+    let header = bitstream
+        .get(1..5)
+        .ok_or("Truncated WebP lossless header")?;
+    let bits = u32::from_le_bytes(header.try_into().unwrap());
+    let (width_minus_one, height_minus_one) = (bits & 0x3FFF, (bits >> 14) & 0x3FFF);
+
+    let mut flags = 0_u8;
+    if (bits >> 28) & 1 == 1 {
+        flags |= 0x10;
+    }
+    if metadata.icc_profile.is_some() {
+        flags |= 0x20;
+    }
+    if metadata.exif.is_some() {
+        flags |= 0x08;
+    }
+    if metadata.xmp.is_some() {
+        flags |= 0x04;
+    }
+    let mut extended_header = vec![flags, 0, 0, 0];
+    extended_header.extend(&width_minus_one.to_le_bytes()[..3]);
+    extended_header.extend(&height_minus_one.to_le_bytes()[..3]);
+
+    let mut body = b"WEBP".to_vec();
+    body.extend(webp_chunk(b"VP8X", &extended_header));
+    if let Some(icc_profile) = &metadata.icc_profile {
+        body.extend(webp_chunk(b"ICCP", icc_profile));
+    }
+    body.extend(webp_chunk(b"VP8L", bitstream));
+    if let Some(exif) = &metadata.exif {
+        body.extend(webp_chunk(b"EXIF", exif));
+    }
+    if let Some(xmp) = &metadata.xmp {
+        body.extend(webp_chunk(b"XMP ", xmp));
+    }
+
+    let mut embedded = b"RIFF".to_vec();
+    embedded.extend((body.len() as u32).to_le_bytes());
+    embedded.extend(body);
+
+    Ok(embedded)
+}
+
+/// Adds metadata to an image encoded as JPEG, PNG or WebP.
+/// Other formats can not carry it and are returned unchanged.
+pub fn embed_metadata(
+    encoded: Vec<u8>,
+    format: ImageFormat,
+    metadata: &ImageMetadata,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if metadata.is_empty() {
+        return Ok(encoded);
+    }
+
+    match format {
+        ImageFormat::Jpeg => embed_jpeg_metadata(&encoded, metadata),
+        ImageFormat::Png => embed_png_metadata(&encoded, metadata),
+        ImageFormat::WebP => embed_webp_metadata(&encoded, metadata),
+        _ => {
+            warn!("{:?} files can not carry metadata, it is dropped", format);
+            Ok(encoded)
+        }
+    }
+}
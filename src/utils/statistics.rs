@@ -1,53 +1,57 @@
-use std::{collections::HashMap, hash::Hash};
+use std::collections::HashMap;
 
-use crate::core::image::Images;
+use log::{info, warn};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 
-fn sort_and_print_map<T>(input_map: &HashMap<T, u32>)
-where
-    T: Copy
-        + Clone
-        + From<u8>
-        + std::fmt::Debug
-        + std::cmp::Ord
-        + std::cmp::PartialEq
-        + Send
-        + Sync,
-{
-    let mut input_vec: Vec<(&T, &u32)> = input_map.iter().collect();
+use crate::core::{
+    image::Images,
+    sample::{Sample, SampleFormat},
+};
+
+use super::json::{json_number, json_string};
+
+/// Bar chart of one histogram, one line per intensity, with bars scaled to the largest count
+fn ascii_histogram(input_map: &HashMap<u8, u32>) -> String {
+    let mut input_vec: Vec<(&u8, &u32)> = input_map.iter().collect();
     input_vec.sort_by(|a, b| a.0.cmp(b.0));
-    let max_val = input_vec.iter().max_by(|a, b| a.1.cmp(b.1)).unwrap().1;
+    let max_val = input_vec.iter().map(|val| *val.1).max().unwrap_or(0).max(1);
 
-    println!("Intensity \t Count");
+    let mut chart = String::from("Intensity \t Count\n");
     for val in input_vec.iter() {
-        print!("{:?}\t", val.0);
-        for _ in 0..((*val.1 as f64 / *max_val as f64) * 40.0) as usize {
-            print!("█");
-        }
-        println!("  [{:?}]", val.1);
+        let bar_length = ((*val.1 as f64 / max_val as f64) * 40.0) as usize;
+        chart.push_str(&format!(
+            "{:?}\t{}  [{:?}]\n",
+            val.0,
+            "█".repeat(bar_length),
+            val.1
+        ));
     }
+
+    chart
 }
 
 ///In an image statistics histogram, we plot the distribution of pixel intensity values
 /// X-Axis: Represents the intensity values (0 to 255).
 /// Y-Axis: Represents the frequency of pixels with that intensity.
-pub fn compute_histogram<T>(image: &Images<T>) -> Vec<HashMap<T, u32>>
+/// Samples wider than 8 bits (u16, f32) are binned into the same 256 intensity levels.
+pub fn compute_histogram<T>(image: &Images<T>) -> Vec<HashMap<u8, u32>>
 where
-    T: Copy + Clone + From<u8> + Eq + Hash + std::cmp::PartialEq + Send + Sync,
+    T: Sample,
 {
-    let mut red_histogram_map: HashMap<T, u32> = HashMap::new();
-    let mut green_histogram_map: HashMap<T, u32> = HashMap::new();
-    let mut blue_histogram_map: HashMap<T, u32> = HashMap::new();
+    let mut red_histogram_map: HashMap<u8, u32> = HashMap::new();
+    let mut green_histogram_map: HashMap<u8, u32> = HashMap::new();
+    let mut blue_histogram_map: HashMap<u8, u32> = HashMap::new();
 
     for index in 0..=255 {
-        red_histogram_map.insert(index.into(), 0);
-        green_histogram_map.insert(index.into(), 0);
-        blue_histogram_map.insert(index.into(), 0);
+        red_histogram_map.insert(index, 0);
+        green_histogram_map.insert(index, 0);
+        blue_histogram_map.insert(index, 0);
     }
 
     for pix in image.get_image().iter() {
-        let red_channel = pix.get_red();
-        let green_channel = pix.get_green();
-        let blue_channel = pix.get_blue();
+        let red_channel = u8::from_unit(pix.get_red().to_unit());
+        let green_channel = u8::from_unit(pix.get_green().to_unit());
+        let blue_channel = u8::from_unit(pix.get_blue().to_unit());
 
         *red_histogram_map.entry(red_channel).or_insert(0) += 1;
         *green_histogram_map.entry(green_channel).or_insert(0) += 1;
@@ -57,48 +61,31 @@ where
     vec![red_histogram_map, green_histogram_map, blue_histogram_map]
 }
 
-pub fn print_histogram<T>(histogram_map: Vec<HashMap<T, u32>>)
-where
-    T: Copy
-        + Clone
-        + From<u8>
-        + std::fmt::Debug
-        + std::cmp::Ord
-        + std::cmp::PartialEq
-        + Send
-        + Sync,
-{
+/// Logs the ASCII chart of the red, green and blue histograms at info level
+pub fn print_histogram(histogram_map: Vec<HashMap<u8, u32>>) {
     for (index, color_map) in histogram_map.iter().enumerate() {
         match index {
-            0 => {
-                println!("Red histogram!");
-                println!("{:?}", sort_and_print_map(color_map));
-            }
-            1 => {
-                println!("Green histogram!");
-                println!("{:?}", sort_and_print_map(color_map));
-            }
-            2 => {
-                println!("Blue histogram!");
-                println!("{:?}", sort_and_print_map(color_map));
-            }
-            _ => {
-                println!("Wrong index. Histogram does not exist")
-            }
+            0 => info!("Red histogram!\n{}", ascii_histogram(color_map).trim_end()),
+            1 => info!(
+                "Green histogram!\n{}",
+                ascii_histogram(color_map).trim_end()
+            ),
+            2 => info!("Blue histogram!\n{}", ascii_histogram(color_map).trim_end()),
+            _ => warn!("Wrong index. Histogram does not exist"),
         }
     }
 }
 
 pub fn compute_mean<T>(image: &Images<T>) -> (f64, f64, f64, f64)
 where
-    T: Copy + Clone + From<u8> + Into<f64> + std::cmp::PartialEq + Send + Sync,
+    T: Sample,
 {
     let mut mean = (0.0, 0.0, 0.0, 0.0);
     for pix in image.get_image() {
-        mean.0 += pix.get_red().into();
-        mean.1 += pix.get_green().into();
-        mean.2 += pix.get_blue().into();
-        mean.3 += pix.get_alpha().into();
+        mean.0 += pix.get_red().to_f64();
+        mean.1 += pix.get_green().to_f64();
+        mean.2 += pix.get_blue().to_f64();
+        mean.3 += pix.get_alpha().to_f64();
     }
     mean.0 /= image.get_image().len() as f64;
     mean.1 /= image.get_image().len() as f64;
@@ -110,16 +97,16 @@ where
 
 pub fn compute_variance<T>(image: &Images<T>) -> (f64, f64, f64, f64)
 where
-    T: Copy + Clone + From<u8> + Into<f64> + std::cmp::PartialEq + Send + Sync,
+    T: Sample,
 {
     let mut variance = (0.0, 0.0, 0.0, 0.0);
     let mean = compute_mean(image);
 
     for pix in image.get_image() {
-        variance.0 += (pix.get_red().into() - mean.0).powi(2);
-        variance.1 += (pix.get_green().into() - mean.1).powi(2);
-        variance.2 += (pix.get_blue().into() - mean.2).powi(2);
-        variance.3 += (pix.get_alpha().into() - mean.3).powi(2);
+        variance.0 += (pix.get_red().to_f64() - mean.0).powi(2);
+        variance.1 += (pix.get_green().to_f64() - mean.1).powi(2);
+        variance.2 += (pix.get_blue().to_f64() - mean.2).powi(2);
+        variance.3 += (pix.get_alpha().to_f64() - mean.3).powi(2);
     }
     variance.0 /= image.get_image().len() as f64;
     variance.1 /= image.get_image().len() as f64;
@@ -128,3 +115,807 @@ where
 
     variance
 }
+
+/// Percentiles computed when none are asked for
+pub const DEFAULT_PERCENTILES: [f64; 6] = [1.0, 5.0, 25.0, 75.0, 95.0, 99.0];
+
+/// Statistics of one channel. Values are in the range of the sample type,
+/// like `compute_mean`, while the histogram is binned into 256 intensity levels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelStatistics {
+    name: &'static str,
+    histogram: [u32; 256],
+    mean: f64,
+    variance: f64,
+    min: f64,
+    max: f64,
+    median: f64,
+    percentiles: Vec<(f64, f64)>,
+}
+
+impl ChannelStatistics {
+    /// Statistics of `values`, with the percentiles in `percentiles`, from 0 to 100.
+    /// The values are reordered in place to find the percentiles.
+    pub fn new(
+        name: &'static str,
+        mut values: Vec<f64>,
+        histogram: [u32; 256],
+        percentiles: &[f64],
+    ) -> Self {
+        let count = values.len().max(1) as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let variance = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        let min = values.iter().copied().min_by(f64::total_cmp);
+        let max = values.iter().copied().max_by(f64::total_cmp);
+
+        Self {
+            name,
+            histogram,
+            mean,
+            variance,
+            min: min.unwrap_or(f64::NAN),
+            max: max.unwrap_or(f64::NAN),
+            median: percentile_of_values(&mut values, 50.0),
+            percentiles: percentiles
+                .iter()
+                .map(|percentile| (*percentile, percentile_of_values(&mut values, *percentile)))
+                .collect(),
+        }
+    }
+
+    /// Statistics of integer samples given as `counts`, the number of samples of each value,
+    /// without holding the samples themselves
+    pub fn from_counts(
+        name: &'static str,
+        counts: &[u64],
+        histogram: [u32; 256],
+        percentiles: &[f64],
+    ) -> Self {
+        let total: u64 = counts.iter().sum();
+        let count = total.max(1) as f64;
+        let mean = counts
+            .iter()
+            .enumerate()
+            .map(|(value, count)| value as f64 * *count as f64)
+            .sum::<f64>()
+            / count;
+        let variance = counts
+            .iter()
+            .enumerate()
+            .map(|(value, count)| (value as f64 - mean).powi(2) * *count as f64)
+            .sum::<f64>()
+            / count;
+        let min = counts.iter().position(|count| *count > 0);
+        let max = counts.iter().rposition(|count| *count > 0);
+
+        Self {
+            name,
+            histogram,
+            mean,
+            variance,
+            min: min.map_or(f64::NAN, |value| value as f64),
+            max: max.map_or(f64::NAN, |value| value as f64),
+            median: percentile_of_counts(counts, total, 50.0),
+            percentiles: percentiles
+                .iter()
+                .map(|percentile| {
+                    (
+                        *percentile,
+                        percentile_of_counts(counts, total, *percentile),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn get_histogram(&self) -> &[u32; 256] {
+        &self.histogram
+    }
+
+    pub fn get_mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn get_variance(&self) -> f64 {
+        self.variance
+    }
+
+    pub fn get_std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    pub fn get_min(&self) -> f64 {
+        self.min
+    }
+
+    pub fn get_max(&self) -> f64 {
+        self.max
+    }
+
+    pub fn get_median(&self) -> f64 {
+        self.median
+    }
+
+    /// Pairs of percentile and value, in the order they were asked for
+    pub fn get_percentiles(&self) -> &[(f64, f64)] {
+        &self.percentiles
+    }
+
+    pub fn get_percentile(&self, percentile: f64) -> Option<f64> {
+        self.percentiles
+            .iter()
+            .find(|(computed, _)| *computed == percentile)
+            .map(|(_, value)| *value)
+    }
+
+    fn to_json(&self) -> String {
+        let histogram: Vec<String> = self
+            .histogram
+            .iter()
+            .map(|count| count.to_string())
+            .collect();
+        let percentiles: Vec<String> = self
+            .percentiles
+            .iter()
+            .map(|(percentile, value)| {
+                format!(
+                    "{}: {}",
+                    json_string(&json_number(*percentile)),
+                    json_number(*value)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"channel\": {}, \"mean\": {}, \"variance\": {}, \"std_dev\": {}, \"min\": {}, \"max\": {}, \"median\": {}, \"percentiles\": {{{}}}, \"histogram\": [{}]}}",
+            json_string(self.name),
+            json_number(self.mean),
+            json_number(self.variance),
+            json_number(self.get_std_dev()),
+            json_number(self.min),
+            json_number(self.max),
+            json_number(self.median),
+            percentiles.join(", "),
+            histogram.join(", ")
+        )
+    }
+}
+
+/// Percentile of sorted values, interpolating linearly between the closest ranks
+/// Indices of the two ordered values around `percentile` among `count` values,
+/// and how far the percentile lies between them
+fn percentile_rank(count: usize, percentile: f64) -> Option<(usize, usize, f64)> {
+    if count == 0 {
+        return None;
+    }
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0) * (count - 1) as f64;
+    let lower = rank.floor() as usize;
+
+    Some((lower, rank.ceil() as usize, rank - lower as f64))
+}
+
+fn percentile_of_values(values: &mut [f64], percentile: f64) -> f64 {
+    let Some((lower, upper, fraction)) = percentile_rank(values.len(), percentile) else {
+        return f64::NAN;
+    };
+    let (_, lower_value, above) = values.select_nth_unstable_by(lower, f64::total_cmp);
+    let lower_value = *lower_value;
+    let upper_value = match upper > lower {
+        true => above
+            .iter()
+            .copied()
+            .min_by(f64::total_cmp)
+            .unwrap_or(lower_value),
+        false => lower_value,
+    };
+
+    lower_value + (upper_value - lower_value) * fraction
+}
+
+fn percentile_of_counts(counts: &[u64], total: u64, percentile: f64) -> f64 {
+    let Some((lower, upper, fraction)) = percentile_rank(total as usize, percentile) else {
+        return f64::NAN;
+    };
+    // Value of the sample at `index` once all samples are sorted
+    let value_at = |index: usize| {
+        let mut seen = 0;
+        counts
+            .iter()
+            .position(|count| {
+                seen += *count as usize;
+                seen > index
+            })
+            .unwrap_or(0) as f64
+    };
+    let lower_value = value_at(lower);
+
+    lower_value + (value_at(upper) - lower_value) * fraction
+}
+
+/// How `ImageStatistics::format` writes the statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatisticsFormat {
+    /// Histogram bar charts followed by the summary of each channel
+    #[default]
+    Ascii,
+    Json,
+    /// One row per channel with the summary, without the histograms
+    Csv,
+    /// One row per intensity level with the count of each channel
+    HistogramCsv,
+}
+
+/// Samples of the red, green, blue or alpha channel, at index 0 to 3, of every pixel.
+/// Gray images repeat their gray samples for red, green and blue,
+/// and images without alpha are opaque.
+fn channel_samples<T>(image: &Images<T>, channel: usize) -> impl Iterator<Item = T> + '_
+where
+    T: Sample,
+{
+    let stored = match (image.get_channels(), channel) {
+        (1, 3) | (3, 3) => None,
+        (1, _) => Some(0),
+        (2, 3) => Some(1),
+        (2, _) => Some(0),
+        _ => Some(channel),
+    };
+
+    image
+        .get_samples()
+        .chunks_exact(image.get_channels() as usize)
+        .map(move |pixel| stored.map_or(T::MAX_VALUE, |index| pixel[index]))
+}
+
+/// Statistics of the red, green, blue and alpha channels of an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageStatistics {
+    width: u32,
+    height: u32,
+    channels: Vec<ChannelStatistics>,
+}
+
+impl ImageStatistics {
+    /// Statistics of the image with the `DEFAULT_PERCENTILES`
+    pub fn new<T>(image: &Images<T>) -> Self
+    where
+        T: Sample,
+    {
+        Self::new_with_percentiles(image, &DEFAULT_PERCENTILES)
+    }
+
+    pub fn new_with_percentiles<T>(image: &Images<T>, percentiles: &[f64]) -> Self
+    where
+        T: Sample,
+    {
+        let names = ["red", "green", "blue", "alpha"];
+        let channels = names
+            .iter()
+            .enumerate()
+            .map(|(channel, name)| {
+                let mut histogram = [0_u32; 256];
+                for sample in channel_samples(image, channel) {
+                    histogram[u8::from_unit(sample.to_unit()) as usize] += 1;
+                }
+                match T::FORMAT {
+                    SampleFormat::U8 | SampleFormat::U16 => {
+                        let mut counts = vec![0_u64; T::MAX_VALUE.to_f64() as usize + 1];
+                        for sample in channel_samples(image, channel) {
+                            counts[sample.to_f64() as usize] += 1;
+                        }
+                        ChannelStatistics::from_counts(name, &counts, histogram, percentiles)
+                    }
+                    SampleFormat::F32 => {
+                        let values = channel_samples(image, channel).map(T::to_f64).collect();
+                        ChannelStatistics::new(name, values, histogram, percentiles)
+                    }
+                }
+            })
+            .collect();
+
+        Self {
+            width: image.get_width(),
+            height: image.get_height(),
+            channels,
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Red, green, blue and alpha, in that order
+    pub fn get_channels(&self) -> &[ChannelStatistics] {
+        &self.channels
+    }
+
+    pub fn get_channel(&self, name: &str) -> Option<&ChannelStatistics> {
+        self.channels.iter().find(|channel| channel.name == name)
+    }
+
+    pub fn format(&self, format: StatisticsFormat) -> String {
+        match format {
+            StatisticsFormat::Ascii => self.to_ascii(),
+            StatisticsFormat::Json => self.to_json(),
+            StatisticsFormat::Csv => self.to_csv(),
+            StatisticsFormat::HistogramCsv => self.histogram_to_csv(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let channels: Vec<String> = self
+            .channels
+            .iter()
+            .map(|channel| channel.to_json())
+            .collect();
+
+        format!(
+            "{{\"width\": {}, \"height\": {}, \"channels\": [{}]}}\n",
+            self.width,
+            self.height,
+            channels.join(", ")
+        )
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut header = String::from("channel,mean,variance,std_dev,min,max,median");
+        for (percentile, _) in self.channels[0].percentiles.iter() {
+            header.push_str(&format!(",p{}", percentile));
+        }
+        let mut csv = header + "\n";
+        for channel in self.channels.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}",
+                channel.name,
+                channel.mean,
+                channel.variance,
+                channel.get_std_dev(),
+                channel.min,
+                channel.max,
+                channel.median
+            ));
+            for (_, value) in channel.percentiles.iter() {
+                csv.push_str(&format!(",{}", value));
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    pub fn histogram_to_csv(&self) -> String {
+        let names: Vec<&str> = self.channels.iter().map(|channel| channel.name).collect();
+        let mut csv = format!("intensity,{}\n", names.join(","));
+        for intensity in 0..256 {
+            let counts: Vec<String> = self
+                .channels
+                .iter()
+                .map(|channel| channel.histogram[intensity].to_string())
+                .collect();
+            csv.push_str(&format!("{},{}\n", intensity, counts.join(",")));
+        }
+
+        csv
+    }
+
+    pub fn to_ascii(&self) -> String {
+        let mut text = String::new();
+        for channel in self.channels.iter() {
+            let histogram: HashMap<u8, u32> = (0..=255_u8)
+                .map(|intensity| (intensity, channel.histogram[intensity as usize]))
+                .collect();
+            text.push_str(&format!("{} histogram\n", channel.name));
+            text.push_str(&ascii_histogram(&histogram));
+            text.push_str(&format!(
+                "{}: mean {:.3}, variance {:.3}, std dev {:.3}, min {}, max {}, median {}\n",
+                channel.name,
+                channel.mean,
+                channel.variance,
+                channel.get_std_dev(),
+                channel.min,
+                channel.max,
+                channel.median
+            ));
+            let percentiles: Vec<String> = channel
+                .percentiles
+                .iter()
+                .map(|(percentile, value)| format!("p{} {}", percentile, value))
+                .collect();
+            if !percentiles.is_empty() {
+                text.push_str(&format!("{}: {}\n", channel.name, percentiles.join(", ")));
+            }
+        }
+
+        text
+    }
+}
+
+/// Per channel and combined values of a metric comparing two images
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityMetric {
+    channels: Vec<f64>,
+    combined: f64,
+}
+
+impl QualityMetric {
+    pub fn new(channels: Vec<f64>, combined: f64) -> Self {
+        Self { channels, combined }
+    }
+
+    /// One value per interleaved channel of the images
+    pub fn get_channels(&self) -> &[f64] {
+        &self.channels
+    }
+
+    pub fn get_combined(&self) -> f64 {
+        self.combined
+    }
+}
+
+/// Side of the Gaussian window of SSIM, with a standard deviation of 1.5 pixels
+const SSIM_WINDOW: usize = 11;
+const SSIM_SIGMA: f64 = 1.5;
+const SSIM_K1: f64 = 0.01;
+const SSIM_K2: f64 = 0.03;
+/// Weights of the scales of MS-SSIM, from the full size image down
+const MS_SSIM_WEIGHTS: [f64; 5] = [0.0448, 0.2856, 0.3001, 0.2363, 0.1333];
+
+/// Samples of each channel as separate planes, in the range of the sample type
+fn channel_planes<T>(image: &Images<T>) -> Vec<Plane>
+where
+    T: Sample,
+{
+    let channels = image.get_channels() as usize;
+    (0..channels)
+        .map(|channel| {
+            let values = image
+                .get_samples()
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .map(|sample| sample.to_f64())
+                .collect();
+            Plane::new(
+                image.get_width() as usize,
+                image.get_height() as usize,
+                values,
+            )
+        })
+        .collect()
+}
+
+/// Planes of both images, after checking they have the same size and channels
+fn compared_planes<T>(
+    reference: &Images<T>,
+    distorted: &Images<T>,
+) -> Result<(Vec<Plane>, Vec<Plane>), Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    if reference.get_width() != distorted.get_width()
+        || reference.get_height() != distorted.get_height()
+        || reference.get_channels() != distorted.get_channels()
+    {
+        return Err(format!(
+            "Can not compare an image of {}x{} with {} channels to one of {}x{} with {} channels",
+            reference.get_width(),
+            reference.get_height(),
+            reference.get_channels(),
+            distorted.get_width(),
+            distorted.get_height(),
+            distorted.get_channels()
+        )
+        .into());
+    }
+    if reference.get_samples().is_empty() {
+        return Err("Can not compare empty images".into());
+    }
+
+    Ok((channel_planes(reference), channel_planes(distorted)))
+}
+
+/// Largest sample value of `T`, the peak signal of PSNR and the dynamic range of SSIM
+fn peak_value<T>() -> f64
+where
+    T: Sample,
+{
+    T::from_unit(1.0).to_f64()
+}
+
+/// Mean squared error between the samples of two images of the same size and channels,
+/// in the range of the sample type. The combined value covers all samples.
+pub fn compute_mse<T>(
+    reference: &Images<T>,
+    distorted: &Images<T>,
+) -> Result<QualityMetric, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let (reference, distorted) = compared_planes(reference, distorted)?;
+    let channels: Vec<f64> = reference
+        .iter()
+        .zip(distorted.iter())
+        .map(|(reference, distorted)| {
+            mean_of(&reference.map(distorted, |x, y| (x - y).powi(2)).values)
+        })
+        .collect();
+    let combined = channels.iter().sum::<f64>() / channels.len() as f64;
+
+    Ok(QualityMetric::new(channels, combined))
+}
+
+/// Peak signal to noise ratio in decibels, with the largest sample value of `T` as the peak.
+/// Identical images give infinity.
+pub fn compute_psnr<T>(
+    reference: &Images<T>,
+    distorted: &Images<T>,
+) -> Result<QualityMetric, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let mse = compute_mse(reference, distorted)?;
+    let peak = peak_value::<T>();
+    let psnr = |mse: f64| 10.0 * (peak * peak / mse).log10();
+
+    Ok(QualityMetric::new(
+        mse.channels.iter().map(|mse| psnr(*mse)).collect(),
+        psnr(mse.combined),
+    ))
+}
+
+/// Structural similarity, the mean of the SSIM map of each channel, 1 for identical images
+/// and lower the more the structure differs. Uses an 11 x 11 Gaussian window, shrunk for smaller
+/// images, and skips the border the window does not fit in.
+/// The combined value is the mean of the channels.
+pub fn compute_ssim<T>(
+    reference: &Images<T>,
+    distorted: &Images<T>,
+) -> Result<QualityMetric, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let (reference, distorted) = compared_planes(reference, distorted)?;
+    let peak = peak_value::<T>();
+    let channels: Vec<f64> = reference
+        .par_iter()
+        .zip(distorted.par_iter())
+        .map(|(reference, distorted)| {
+            let (ssim, _) = ssim_maps(reference, distorted, peak);
+            mean_of(&ssim.values)
+        })
+        .collect();
+    let combined = mean_of(&channels);
+
+    Ok(QualityMetric::new(channels, combined))
+}
+
+/// SSIM of every window position, averaged over the channels, as a one channel image.
+/// The map is smaller than the images by the window size less one, as in `compute_ssim`,
+/// and its values are clamped to 0.0 to 1.0 so it can be written like any image.
+pub fn compute_ssim_map<T>(
+    reference: &Images<T>,
+    distorted: &Images<T>,
+) -> Result<Images<f32>, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let (reference, distorted) = compared_planes(reference, distorted)?;
+    let peak = peak_value::<T>();
+    let maps: Vec<Plane> = reference
+        .par_iter()
+        .zip(distorted.par_iter())
+        .map(|(reference, distorted)| ssim_maps(reference, distorted, peak).0)
+        .collect();
+
+    let samples = (0..maps[0].values.len())
+        .map(|index| {
+            let ssim = maps.iter().map(|map| map.values[index]).sum::<f64>() / maps.len() as f64;
+            ssim.clamp(0.0, 1.0) as f32
+        })
+        .collect();
+
+    Ok(Images::from_samples(
+        maps[0].width as u32,
+        maps[0].height as u32,
+        1,
+        samples,
+    ))
+}
+
+/// Multi-scale structural similarity over five scales, halving the images between them,
+/// with the weights of Wang, Simoncelli and Bovik (2003). Images too small for five scales
+/// use the scales that fit, with their weights normalized.
+/// The combined value is the mean of the channels.
+pub fn compute_ms_ssim<T>(
+    reference: &Images<T>,
+    distorted: &Images<T>,
+) -> Result<QualityMetric, Box<dyn std::error::Error>>
+where
+    T: Sample,
+{
+    let (reference, distorted) = compared_planes(reference, distorted)?;
+    let peak = peak_value::<T>();
+
+    let smallest_side = reference[0].width.min(reference[0].height);
+    let mut scales = 1;
+    while scales < MS_SSIM_WEIGHTS.len() && smallest_side >> scales >= SSIM_WINDOW {
+        scales += 1;
+    }
+    let weights = &MS_SSIM_WEIGHTS[..scales];
+    let weight_sum: f64 = weights.iter().sum();
+
+    let channels: Vec<f64> = reference
+        .par_iter()
+        .zip(distorted.par_iter())
+        .map(|(reference, distorted)| {
+            let mut reference = reference.clone();
+            let mut distorted = distorted.clone();
+            let mut ms_ssim = 1.0;
+            for (scale, weight) in weights.iter().enumerate() {
+                let (ssim, contrast_structure) = ssim_maps(&reference, &distorted, peak);
+                // The last scale adds luminance, the others only contrast and structure
+                if scale + 1 == scales {
+                    ms_ssim *= mean_of(&ssim.values).max(0.0).powf(weight / weight_sum);
+                } else {
+                    ms_ssim *= mean_of(&contrast_structure.values)
+                        .max(0.0)
+                        .powf(weight / weight_sum);
+                    reference = reference.downsample();
+                    distorted = distorted.downsample();
+                }
+            }
+            ms_ssim
+        })
+        .collect();
+    let combined = mean_of(&channels);
+
+    Ok(QualityMetric::new(channels, combined))
+}
+
+fn mean_of(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+/// One channel of samples
+#[derive(Debug, Clone)]
+struct Plane {
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+}
+
+impl Plane {
+    fn new(width: usize, height: usize, values: Vec<f64>) -> Self {
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// Averages blocks of 2 x 2, dropping an odd last row or column
+    fn downsample(&self) -> Self {
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let at = |x: usize, y: usize| {
+            self.values[y.min(self.height - 1) * self.width + x.min(self.width - 1)]
+        };
+        let values = (0..height)
+            .flat_map(|y| {
+                (0..width).map(move |x| {
+                    (at(2 * x, 2 * y)
+                        + at(2 * x + 1, 2 * y)
+                        + at(2 * x, 2 * y + 1)
+                        + at(2 * x + 1, 2 * y + 1))
+                        / 4.0
+                })
+            })
+            .collect();
+
+        Self::new(width, height, values)
+    }
+
+    fn map(&self, other: &Plane, function: impl Fn(f64, f64) -> f64) -> Plane {
+        let values = self
+            .values
+            .iter()
+            .zip(other.values.iter())
+            .map(|(a, b)| function(*a, *b))
+            .collect();
+
+        Plane::new(self.width, self.height, values)
+    }
+
+    /// Separable convolution with `kernel` at the positions where it fits entirely
+    fn filter_valid(&self, kernel: &[f64]) -> Plane {
+        let size = kernel.len();
+        let width = self.width + 1 - size;
+        let height = self.height + 1 - size;
+
+        let mut rows: Vec<f64> = Vec::with_capacity(width * self.height);
+        for y in 0..self.height {
+            let row = &self.values[y * self.width..(y + 1) * self.width];
+            for x in 0..width {
+                rows.push(
+                    kernel
+                        .iter()
+                        .zip(&row[x..x + size])
+                        .map(|(k, v)| k * v)
+                        .sum(),
+                );
+            }
+        }
+        let mut values: Vec<f64> = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                values.push(
+                    kernel
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, k)| k * rows[(y + offset) * width + x])
+                        .sum(),
+                );
+            }
+        }
+
+        Plane::new(width, height, values)
+    }
+}
+
+/// Normalized Gaussian weights of an odd `size`
+fn gaussian_window(size: usize) -> Vec<f64> {
+    let center = (size / 2) as f64;
+    let weights: Vec<f64> = (0..size)
+        .map(|index| (-((index as f64 - center).powi(2)) / (2.0 * SSIM_SIGMA * SSIM_SIGMA)).exp())
+        .collect();
+    let sum: f64 = weights.iter().sum();
+
+    weights.iter().map(|weight| weight / sum).collect()
+}
+
+/// SSIM map and contrast-structure map of one channel
+fn ssim_maps(x: &Plane, y: &Plane, peak: f64) -> (Plane, Plane) {
+    // The window shrinks to the largest odd size fitting in small images
+    let mut size = SSIM_WINDOW.min(x.width).min(x.height);
+    if size.is_multiple_of(2) {
+        size -= 1;
+    }
+    let window = gaussian_window(size.max(1));
+
+    let mean_x = x.filter_valid(&window);
+    let mean_y = y.filter_valid(&window);
+    let mean_xx = x.map(x, |a, b| a * b).filter_valid(&window);
+    let mean_yy = y.map(y, |a, b| a * b).filter_valid(&window);
+    let mean_xy = x.map(y, |a, b| a * b).filter_valid(&window);
+
+    let c1 = (SSIM_K1 * peak).powi(2);
+    let c2 = (SSIM_K2 * peak).powi(2);
+    let mut ssim = Vec::with_capacity(mean_x.values.len());
+    let mut contrast_structure = Vec::with_capacity(mean_x.values.len());
+    for index in 0..mean_x.values.len() {
+        let (mx, my) = (mean_x.values[index], mean_y.values[index]);
+        let variance_x = mean_xx.values[index] - mx * mx;
+        let variance_y = mean_yy.values[index] - my * my;
+        let covariance = mean_xy.values[index] - mx * my;
+
+        let cs = (2.0 * covariance + c2) / (variance_x + variance_y + c2);
+        let luminance = (2.0 * mx * my + c1) / (mx * mx + my * my + c1);
+        contrast_structure.push(cs);
+        ssim.push(luminance * cs);
+    }
+
+    (
+        Plane::new(mean_x.width, mean_x.height, ssim),
+        Plane::new(mean_x.width, mean_x.height, contrast_structure),
+    )
+}
@@ -0,0 +1,4 @@
+/// Path in the temp folder unique to the test process, so concurrent runs do not share files
+pub fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("image_processor_{}_{}", std::process::id(), name))
+}
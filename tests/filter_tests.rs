@@ -63,9 +63,10 @@ where
 #[cfg(test)]
 mod tests {
     use image_processor::{
-        core::{image::Images, operations::Operation, pixel::Pixels},
+        core::{image::Images, operations::Operation, pixel::Pixels, sample::RoundingMode},
         filters::{
             blur::{Blur, SmoothingKernelChoices},
+            color_adjustment::{HueShift, Saturation, Vibrance},
             edge_detection::{EdgeDetectingKernelChoices, EdgeDetection},
             gray_scale::{GrayScale, GrayScaleAlgorithms},
             sharpen::{Sharpen, SharpeningKernelChoices},
@@ -192,7 +193,7 @@ mod tests {
 
         let gray_image: Images<u8> = GrayScale::new(GrayScaleAlgorithms::Average).apply(&img);
         println!("{:?}", gray_image);
-        let new_image = Images::new(1, 1, 3, vec![Pixels::new(181, 181, 181, 255)]);
+        let new_image = Images::new(1, 1, 1, vec![Pixels::new(181, 181, 181, 255)]);
         assert_eq!(gray_image, new_image);
     }
 
@@ -201,7 +202,150 @@ mod tests {
         let img = Images::new(1, 1, 3, vec![Pixels::new(255, 200, 100, 255)]);
 
         let gray_image: Images<u8> = GrayScale::new(GrayScaleAlgorithms::Luminosity).apply(&img);
-        let new_image = Images::new(1, 1, 3, vec![Pixels::new(68, 68, 68, 255)]);
+        let new_image = Images::new(1, 1, 1, vec![Pixels::new(68, 68, 68, 255)]);
         assert_eq!(gray_image, new_image);
     }
+
+    #[test]
+    fn hue_shift_filter_test() {
+        let img = Images::new(1, 1, 4, vec![Pixels::new(255, 0, 0, 100)]);
+
+        let shifted_image: Images<u8> = HueShift::new(120.0).apply(&img);
+        let new_image = Images::new(1, 1, 4, vec![Pixels::new(0, 255, 0, 100)]);
+        assert_eq!(shifted_image, new_image);
+    }
+
+    #[test]
+    fn saturation_filter_test() {
+        let img = Images::new(1, 1, 4, vec![Pixels::new(200, 100, 50, 200)]);
+
+        let desaturated_image: Images<u8> = Saturation::new(0.0).apply(&img);
+        let new_image = Images::new(1, 1, 4, vec![Pixels::new(125, 125, 125, 200)]);
+        assert_eq!(desaturated_image, new_image);
+
+        let unchanged_image: Images<u8> = Saturation::new(1.0).apply(&img);
+        assert_eq!(unchanged_image, img);
+    }
+
+    #[test]
+    fn vibrance_filter_test() {
+        let img = Images::new(
+            3,
+            1,
+            4,
+            vec![
+                Pixels::new(200, 150, 100, 255),
+                Pixels::new(255, 0, 0, 255),
+                Pixels::new(90, 90, 90, 255),
+            ],
+        );
+
+        let vibrant_image: Images<u8> = Vibrance::new(1.0).apply(&img);
+        let new_image = Images::new(
+            3,
+            1,
+            4,
+            vec![
+                Pixels::new(200, 125, 50, 255),
+                Pixels::new(255, 0, 0, 255),
+                Pixels::new(90, 90, 90, 255),
+            ],
+        );
+        assert_eq!(vibrant_image, new_image);
+    }
+
+    #[test]
+    fn blur_filter_linear_light_test() {
+        let img = Images::new(
+            3,
+            3,
+            3,
+            vec![
+                Pixels::new(255, 255, 255, 255),
+                Pixels::new(0, 0, 0, 255),
+                Pixels::new(0, 0, 0, 255),
+                Pixels::new(0, 0, 0, 255),
+                Pixels::new(255, 255, 255, 255),
+                Pixels::new(0, 0, 0, 255),
+                Pixels::new(0, 0, 0, 255),
+                Pixels::new(0, 0, 0, 255),
+                Pixels::new(0, 0, 0, 255),
+            ],
+        );
+
+        let gamma_img: Images<u8> = Blur::new(SmoothingKernelChoices::BoxBlur).apply(&img);
+        let expected_gamma_img = Images::new(1, 1, 3, vec![Pixels::new(56, 56, 56, 255)]);
+        assert_eq!(gamma_img, expected_gamma_img);
+
+        let linear_img: Images<u8> = Blur::new(SmoothingKernelChoices::BoxBlur)
+            .with_linear_light(true)
+            .apply(&img);
+        let expected_linear_img = Images::new(1, 1, 3, vec![Pixels::new(130, 130, 130, 255)]);
+        assert_eq!(linear_img, expected_linear_img);
+    }
+
+    #[test]
+    fn grayscale_filter_f32_test() {
+        let img: Images<f32> = Images::new(1, 1, 4, vec![Pixels::new(1.0, 0.5, 0.0, 0.25)]);
+
+        let gray_image = GrayScale::new(GrayScaleAlgorithms::Average).apply(&img);
+        let new_image = Images::new(1, 1, 2, vec![Pixels::new(0.5, 0.5, 0.5, 0.25)]);
+        assert_eq!(gray_image, new_image);
+    }
+
+    #[test]
+    fn blur_filter_u16_test() {
+        let img: Images<u16> = Images::new(3, 3, 3, vec![Pixels::new(60000, 900, 0, 65535); 9]);
+
+        let blurred_img = Blur::new(SmoothingKernelChoices::BoxBlur).apply(&img);
+        let new_image = Images::new(1, 1, 3, vec![Pixels::new(26666, 400, 0, 65535)]);
+        assert_eq!(blurred_img, new_image);
+    }
+
+    #[test]
+    fn sharpen_filter_f32_test() {
+        let img: Images<f32> = Images::new(3, 3, 3, vec![Pixels::new(0.5, 0.25, 0.0, 1.0); 9]);
+
+        let sharpen_image = Sharpen::new(SharpeningKernelChoices::Basic).apply(&img);
+        let new_image = Images::new(1, 1, 3, vec![Pixels::new(0.5, 0.25, 0.0, 1.0)]);
+        assert_eq!(sharpen_image, new_image);
+    }
+
+    #[test]
+    fn blur_filter_rounding_test() {
+        let img: Images<u16> = Images::new(3, 3, 3, vec![Pixels::new(60000, 900, 1, 65535); 9]);
+
+        let nearest_img = Blur::new(SmoothingKernelChoices::BoxBlur)
+            .with_rounding(RoundingMode::Nearest)
+            .apply(&img);
+        let ceil_img = Blur::new(SmoothingKernelChoices::BoxBlur)
+            .with_rounding(RoundingMode::Ceil)
+            .apply(&img);
+        assert_eq!(
+            nearest_img,
+            Images::new(1, 1, 3, vec![Pixels::new(26667, 400, 0, 65535)])
+        );
+        assert_eq!(
+            ceil_img,
+            Images::new(1, 1, 3, vec![Pixels::new(26667, 400, 1, 65535)])
+        );
+    }
+
+    #[test]
+    fn sharpen_filter_saturation_test() {
+        // Bright center on a black background overshoots far beyond the u8 range
+        let mut img: Images<u8> = Images::new(3, 3, 3, vec![Pixels::new(0, 0, 0, 255); 9]);
+        img.set_pixel_at(1, 1, Pixels::new(250, 0, 0, 255)).unwrap();
+
+        let sharpen_image = Sharpen::new(SharpeningKernelChoices::HighPass).apply(&img);
+        let edge_image = EdgeDetection::new(EdgeDetectingKernelChoices::SobelX).apply(&img);
+        assert_eq!(
+            sharpen_image,
+            Images::new(1, 1, 3, vec![Pixels::new(255, 0, 0, 255)])
+        );
+        assert_eq!(
+            edge_image,
+            Images::new(1, 1, 3, vec![Pixels::new(0, 0, 0, 255)])
+        );
+    }
 }
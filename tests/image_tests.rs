@@ -1,10 +1,18 @@
 #[cfg(test)]
 mod tests {
-    use image_processor::core::{image::Images, pixel::Pixels};
+    use image::{DynamicImage, ImageBuffer, Luma, Rgb, Rgba};
+    use image_processor::{
+        core::{
+            image::Images,
+            pixel::Pixels,
+            sample::{RoundingMode, Sample},
+        },
+        utils::image_io::OutputFormat,
+    };
 
     #[test]
     fn pixel_add_test() {
-        let pix1 = Pixels::new(10, 20, 30, 255);
+        let pix1: Pixels<u8> = Pixels::new(10, 20, 30, 255);
         let pix2 = Pixels::new(50, 60, 70, 255);
 
         let pix_computed = pix1 + pix2;
@@ -15,7 +23,7 @@ mod tests {
 
     #[test]
     fn pixel_sub_test() {
-        let pix1 = Pixels::new(10, 20, 30, 255);
+        let pix1: Pixels<u8> = Pixels::new(10, 20, 30, 255);
         let pix2 = Pixels::new(50, 60, 70, 155);
 
         let pix_computed = pix2 - pix1;
@@ -26,7 +34,7 @@ mod tests {
 
     #[test]
     fn pixel_mulf64_test() {
-        let pix1 = Pixels::new(10, 20, 30, 255);
+        let pix1: Pixels<u8> = Pixels::new(10, 20, 30, 255);
         let num = 10.0;
 
         let pix_computed = pix1 * num;
@@ -37,7 +45,7 @@ mod tests {
 
     #[test]
     fn image_get_pixel_at_location_test() {
-        let img = Images::new(
+        let img: Images<u8> = Images::new(
             2,
             2,
             3,
@@ -57,7 +65,7 @@ mod tests {
 
     #[test]
     fn image_get_pixel_at_location_out_of_bounds_test() {
-        let img = Images::new(
+        let img: Images<u8> = Images::new(
             2,
             2,
             3,
@@ -74,4 +82,160 @@ mod tests {
 
         assert_eq!(pix_computed, expected_pix);
     }
+
+    #[test]
+    fn pixel_u16_range_test() {
+        let pix1: Pixels<u16> = Pixels::new(40000, 1000, 0, 65535);
+        let pix2: Pixels<u16> = Pixels::new(30000, 2000, 0, 0);
+
+        let pix_computed = pix1 + pix2;
+        let expected_pix = Pixels::new(65535, 3000, 0, 65535);
+
+        assert_eq!(pix_computed, expected_pix);
+        assert_eq!(Pixels::<u16>::default(), Pixels::new(0, 0, 0, 65535));
+    }
+
+    #[test]
+    fn pixel_f32_range_test() {
+        let pix1: Pixels<f32> = Pixels::new(0.25, 0.5, 0.75, 1.0);
+
+        let pix_computed = pix1 * 2.0;
+        let expected_pix = Pixels::new(0.5, 1.0, 1.0, 1.0);
+
+        assert_eq!(pix_computed, expected_pix);
+        assert_eq!(Pixels::<f32>::default(), Pixels::new(0.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn sample_unit_conversion_test() {
+        assert_eq!(u8::from_unit(65535_u16.to_unit()), 255);
+        assert_eq!(u16::from_unit(255_u8.to_unit()), 65535);
+        assert_eq!(u16::from_unit(0.5_f32.to_unit()), 32768);
+        assert_eq!(f32::from_unit(51_u8.to_unit()), 0.2);
+    }
+
+    #[test]
+    fn image_channel_storage_test() {
+        let mut img: Images<u8> = Images::new(
+            2,
+            1,
+            2,
+            vec![Pixels::new(10, 20, 30, 40), Pixels::new(50, 60, 70, 80)],
+        );
+        assert_eq!(img.get_samples(), &[10, 40, 50, 80]);
+        assert_eq!(img.get_pixel_at(1, 0).unwrap(), Pixels::new(50, 50, 50, 80));
+
+        img.set_pixel_at(0, 0, Pixels::new(90, 0, 0, 255)).unwrap();
+        assert_eq!(img.get_samples(), &[90, 255, 50, 80]);
+        assert!(img.set_pixel_at(2, 0, Pixels::new(0, 0, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn pixel_saturating_arithmetic_test() {
+        let pix1: Pixels<u8> = Pixels::new(200, 10, 255, 255);
+        let pix2 = Pixels::new(100, 20, 1, 255);
+
+        assert_eq!(pix1.clone() + pix2.clone(), Pixels::new(255, 30, 255, 255));
+        assert_eq!(pix1.clone() - pix2.clone(), Pixels::new(100, 0, 254, 0));
+        assert_eq!(pix1.clone() * -1.0, Pixels::new(0, 0, 0, 0));
+        assert_eq!(pix2.clone() * u32::MAX, Pixels::new(255, 255, 255, 255));
+
+        let wide1: Pixels<u16> = Pixels::new(65000, 0, 1, 65535);
+        let wide2 = Pixels::new(1000, 1, 0, 65535);
+        assert_eq!(wide1 + wide2, Pixels::new(65535, 1, 1, 65535));
+    }
+
+    #[test]
+    fn pixel_div_by_zero_test() {
+        let pix: Pixels<u8> = Pixels::new(0, 1, 128, 255);
+
+        assert_eq!(pix.clone() / 0, Pixels::new(0, 255, 255, 255));
+        assert_eq!(pix.clone().checked_div(0, RoundingMode::Truncate), None);
+        assert_eq!(
+            pix.checked_div(2, RoundingMode::Truncate),
+            Some(Pixels::new(0, 0, 64, 127))
+        );
+
+        let float_pix: Pixels<f32> = Pixels::new(0.0, 0.5, 1.0, 1.0);
+        assert_eq!(float_pix / 0, Pixels::new(0.0, 1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn pixel_rounding_mode_test() {
+        let pix: Pixels<u8> = Pixels::new(3, 5, 7, 255);
+
+        assert_eq!(
+            pix.clone().scale(0.5, RoundingMode::Truncate),
+            Pixels::new(1, 2, 3, 127)
+        );
+        assert_eq!(
+            pix.clone().scale(0.5, RoundingMode::Nearest),
+            Pixels::new(2, 3, 4, 128)
+        );
+        assert_eq!(
+            pix.clone().scale(0.5, RoundingMode::Floor),
+            Pixels::new(1, 2, 3, 127)
+        );
+        assert_eq!(
+            pix.checked_div(2, RoundingMode::Ceil),
+            Some(Pixels::new(2, 3, 4, 128))
+        );
+
+        assert_eq!(u8::from_f64_rounded(-0.5, RoundingMode::Ceil), 0);
+        assert_eq!(u16::from_f64_rounded(65534.5, RoundingMode::Nearest), 65535);
+        assert_eq!(f32::from_f64_rounded(0.25, RoundingMode::Ceil), 0.25);
+        assert_eq!(f32::from_f64(f64::NAN), 0.0);
+    }
+
+    #[test]
+    fn image_buffer_zero_copy_test() {
+        let buffer: ImageBuffer<Rgb<u8>, Vec<u8>> =
+            ImageBuffer::from_raw(2, 1, vec![1, 2, 3, 4, 5, 6]).unwrap();
+        let buffer_ptr = buffer.as_raw().as_ptr();
+
+        let img: Images<u8> = buffer.into();
+        assert_eq!(img.get_channels(), 3);
+        assert_eq!(img.get_samples().as_ptr(), buffer_ptr);
+        assert_eq!(img.get_pixel_at(1, 0).unwrap(), Pixels::new(4, 5, 6, 255));
+
+        assert!(ImageBuffer::<Rgba<u8>, Vec<u8>>::try_from(img.clone()).is_err());
+
+        let dynamic_image = DynamicImage::try_from(img).unwrap();
+        assert_eq!(dynamic_image.as_bytes().as_ptr(), buffer_ptr);
+
+        let img: Images<u8> = dynamic_image.into();
+        let round_trip: ImageBuffer<Rgb<u8>, Vec<u8>> = img.try_into().unwrap();
+        assert_eq!(round_trip.as_raw().as_ptr(), buffer_ptr);
+    }
+
+    #[test]
+    fn dynamic_image_conversion_test() {
+        let buffer: ImageBuffer<Luma<u16>, Vec<u16>> =
+            ImageBuffer::from_raw(2, 1, vec![0, 65535]).unwrap();
+
+        let img: Images<u8> = DynamicImage::ImageLuma16(buffer).into();
+        assert_eq!(img, Images::from_samples(2, 1, 1, vec![0, 255]));
+
+        let float_img: Images<f32> = DynamicImage::try_from(img).unwrap().into();
+        assert_eq!(float_img, Images::from_samples(2, 1, 1, vec![0.0, 1.0]));
+    }
+
+    #[test]
+    fn image_planar_layout_test() {
+        let img: Images<u8> = Images::from_samples(2, 1, 3, vec![1, 2, 3, 4, 5, 6]);
+
+        let planes = img.to_planar();
+        assert_eq!(planes, vec![1, 4, 2, 5, 3, 6]);
+        assert_eq!(Images::from_planar(2, 1, 3, &planes), img);
+    }
+
+    #[test]
+    fn image_bytes_round_trip_test() {
+        let img: Images<u16> = Images::from_samples(2, 1, 3, vec![1, 2, 3, 40000, 50000, 65535]);
+
+        let bytes = img.to_bytes(OutputFormat::Png).unwrap();
+        assert_eq!(&bytes[1..4], b"PNG");
+        assert_eq!(Images::<u16>::from_bytes(&bytes).unwrap(), img);
+        assert!(Images::<u16>::from_bytes(b"not an image").is_err());
+    }
 }
@@ -0,0 +1,214 @@
+use image_processor::core::{image::Images, pixel::Pixels};
+
+fn common_setup_uniform() -> Images<u8> {
+    Images::new(4, 4, 3, vec![Pixels::new(200, 100, 50, 255); 16])
+}
+
+fn common_setup_gradient() -> Images<u8> {
+    let pixels = (0..16)
+        .map(|index| Pixels::new((index % 4) * 60, (index / 4) * 60, 0, 255))
+        .collect();
+    Images::new(4, 4, 3, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use image_processor::{
+        core::{
+            image::Images,
+            mask::{MaskRegion, Masked},
+            operations::Operation,
+            pixel::Pixels,
+        },
+        filters::{
+            blur::{Blur, SmoothingKernelChoices},
+            gamma_correction::GammaCorrection,
+            gray_scale::{GrayScale, GrayScaleAlgorithms},
+        },
+        transformations::crop::Crop,
+    };
+
+    use super::*;
+
+    #[test]
+    fn masked_rectangle_test() {
+        let img = common_setup_uniform();
+
+        let masked_img = Masked::new(
+            Box::new(GrayScale::new(GrayScaleAlgorithms::Average)),
+            MaskRegion::Rectangle {
+                top_left: (0, 0),
+                width: 2,
+                height: 1,
+            },
+        )
+        .apply(&img);
+
+        assert_eq!(masked_img.get_width(), 4);
+        assert_eq!(masked_img.get_height(), 4);
+        assert_eq!(
+            masked_img.get_pixel_at(0, 0).unwrap(),
+            Pixels::new(116, 116, 116, 255)
+        );
+        assert_eq!(
+            masked_img.get_pixel_at(1, 0).unwrap(),
+            Pixels::new(116, 116, 116, 255)
+        );
+        assert_eq!(
+            masked_img.get_pixel_at(2, 0).unwrap(),
+            Pixels::new(200, 100, 50, 255)
+        );
+        assert_eq!(
+            masked_img.get_pixel_at(0, 1).unwrap(),
+            Pixels::new(200, 100, 50, 255)
+        );
+    }
+
+    #[test]
+    fn masked_rectangle_large_test() {
+        let img = common_setup_uniform();
+
+        // The far corner of the rectangle lies beyond the u32 range
+        let masked_img = Masked::new(
+            Box::new(GrayScale::new(GrayScaleAlgorithms::Average)),
+            MaskRegion::Rectangle {
+                top_left: (2, 2),
+                width: u32::MAX,
+                height: u32::MAX,
+            },
+        )
+        .apply(&img);
+
+        assert_eq!(
+            masked_img.get_pixel_at(3, 3).unwrap(),
+            Pixels::new(116, 116, 116, 255)
+        );
+        assert_eq!(
+            masked_img.get_pixel_at(1, 3).unwrap(),
+            Pixels::new(200, 100, 50, 255)
+        );
+    }
+
+    #[test]
+    fn masked_ellipse_test() {
+        let img = common_setup_uniform();
+
+        let masked_img = Masked::new(
+            Box::new(GrayScale::new(GrayScaleAlgorithms::Average)),
+            MaskRegion::Ellipse {
+                center: (0, 0),
+                radius_x: 1,
+                radius_y: 1,
+            },
+        )
+        .apply(&img);
+
+        assert_eq!(
+            masked_img.get_pixel_at(1, 0).unwrap(),
+            Pixels::new(116, 116, 116, 255)
+        );
+        assert_eq!(
+            masked_img.get_pixel_at(1, 1).unwrap(),
+            Pixels::new(200, 100, 50, 255)
+        );
+    }
+
+    #[test]
+    fn masked_image_blend_test() {
+        let img = common_setup_uniform();
+        let mask: Images<u8> = Images::new(
+            2,
+            1,
+            3,
+            vec![Pixels::new(0, 0, 0, 255), Pixels::new(255, 255, 255, 255)],
+        );
+
+        let masked_img = Masked::new(
+            Box::new(GrayScale::new(GrayScaleAlgorithms::Average)),
+            MaskRegion::Image(mask),
+        )
+        .apply(&img);
+
+        assert_eq!(
+            masked_img.get_pixel_at(1, 3).unwrap(),
+            Pixels::new(200, 100, 50, 255)
+        );
+        assert_eq!(
+            masked_img.get_pixel_at(3, 3).unwrap(),
+            Pixels::new(116, 116, 116, 255)
+        );
+    }
+
+    #[test]
+    fn masked_size_changing_filter_test() {
+        let img = common_setup_uniform();
+
+        let masked_img = Masked::new(
+            Box::new(Blur::new(SmoothingKernelChoices::BoxBlur)),
+            MaskRegion::Rectangle {
+                top_left: (0, 0),
+                width: 4,
+                height: 4,
+            },
+        )
+        .apply(&img);
+
+        assert_eq!(masked_img.get_width(), 4);
+        assert_eq!(masked_img.get_height(), 4);
+        // Border pixels are not covered by the 3x3 kernel and keep their original value
+        assert_eq!(
+            masked_img.get_pixel_at(0, 0).unwrap(),
+            Pixels::new(200, 100, 50, 255)
+        );
+        assert_eq!(
+            masked_img.get_pixel_at(1, 1).unwrap(),
+            Pixels::new(88, 44, 22, 255)
+        );
+    }
+
+    #[test]
+    fn masked_linear_light_blend_test() {
+        let img: Images<u8> = Images::new(1, 1, 4, vec![Pixels::new(0, 0, 0, 255)]);
+        let mask: Images<u8> = Images::new(1, 1, 3, vec![Pixels::new(128, 128, 128, 255)]);
+
+        // Gamma 0 maps every channel to 255
+        let gamma_blend = Masked::new(
+            Box::new(GammaCorrection::new(0.0)),
+            MaskRegion::Image(mask.clone()),
+        )
+        .apply(&img);
+        assert_eq!(
+            gamma_blend.get_pixel_at(0, 0).unwrap(),
+            Pixels::new(128, 128, 128, 255)
+        );
+
+        let linear_blend =
+            Masked::new(Box::new(GammaCorrection::new(0.0)), MaskRegion::Image(mask))
+                .with_linear_light(true)
+                .apply(&img);
+        assert_eq!(
+            linear_blend.get_pixel_at(0, 0).unwrap(),
+            Pixels::new(188, 188, 188, 255)
+        );
+    }
+
+    #[test]
+    fn masked_crop_test() {
+        let img = common_setup_gradient();
+
+        // The cropped corner is blended back where it was cut from, so nothing moves
+        let masked_img = Masked::new(
+            Box::new(Crop::new((0, 0), 2, 2)),
+            MaskRegion::Rectangle {
+                top_left: (0, 0),
+                width: 4,
+                height: 4,
+            },
+        )
+        .apply(&img);
+
+        assert_eq!(masked_img.get_width(), 4);
+        assert_eq!(masked_img.get_height(), 4);
+        assert_eq!(masked_img.get_image(), img.get_image());
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    process::{Child, Command, Stdio},
+};
+
+/// Server binary listening on a free port, killed when dropped
+struct Server {
+    child: Child,
+    address: String,
+}
+
+impl Server {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_server"))
+            .arg("127.0.0.1:0")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        let address = line.trim().rsplit(' ').next().unwrap().to_string();
+        // Keeps reading the log, so the server never blocks on a full pipe
+        std::thread::spawn(move || for _ in stdout.lines() {});
+
+        Self { child, address }
+    }
+
+    /// Sends a raw request and returns the status code and the body of the response
+    fn send(&self, head: &str, body: &[u8]) -> (u16, Vec<u8>) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(body).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+
+        let head_end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap();
+        let status = String::from_utf8_lossy(&response[9..12]).parse().unwrap();
+
+        (status, response[head_end + 4..].to_vec())
+    }
+
+    fn post(&self, target: &str, body: &[u8]) -> (u16, Vec<u8>) {
+        let head = format!(
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n",
+            target,
+            body.len()
+        );
+        self.send(&head, body)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image_processor::{
+        core::{image::Images, pipeline::parse_pipeline},
+        utils::image_io::{decode_image, encode_image, OutputFormat, OutputOptions, ReadOptions},
+        utils::metadata::ImageMetadata,
+    };
+
+    use super::*;
+
+    #[test]
+    fn parse_pipeline_test() {
+        let image: Images<u8> = Images::from_samples(4, 2, 3, (0..24).collect());
+
+        let operations =
+            parse_pipeline::<u8>(" resize:2,1 ; flip_horizontal;gamma:1.0;", 64).unwrap();
+        let mut result = image.clone();
+        for operation in operations.iter() {
+            result = operation.apply(&result);
+        }
+
+        assert_eq!(operations.len(), 3);
+        assert_eq!(operations[0].output_size((4, 2)), Some((2, 1)));
+        assert_eq!(operations[1].output_size((2, 1)), Some((2, 1)));
+        assert_eq!(
+            result,
+            Images::from_samples(2, 1, 3, vec![6, 7, 8, 0, 1, 2])
+        );
+        assert!(parse_pipeline::<u8>("", 64).unwrap().is_empty());
+        assert!(parse_pipeline::<u8>("blur:gaussian;sharpen:high_pass", 64).is_ok());
+        assert!(parse_pipeline::<u8>("resize:65,1", 64).is_err());
+        assert!(parse_pipeline::<u8>("resize:2", 64).is_err());
+        assert!(parse_pipeline::<u8>("blur:median", 64).is_err());
+        assert!(parse_pipeline::<u8>("gamma:NaN", 64).is_err());
+        assert!(parse_pipeline::<u8>("swirl", 64).is_err());
+    }
+
+    #[test]
+    fn server_test() {
+        let server = Server::start();
+        let image: Images<u8> = Images::from_samples(4, 4, 3, vec![120; 48]);
+        let png = encode_image(
+            &image,
+            &OutputOptions::new().with_format(OutputFormat::Png),
+            &ImageMetadata::default(),
+        )
+        .unwrap();
+        let tiny = encode_image(
+            &Images::<u8>::from_samples(1, 1, 3, vec![120; 3]),
+            &OutputOptions::new().with_format(OutputFormat::Png),
+            &ImageMetadata::default(),
+        )
+        .unwrap();
+
+        let (process_status, processed) =
+            server.post("/process?pipeline=resize%3A2%2C2%3Bgamma%3A1.0", &png);
+        let (stats_status, stats) = server.post("/stats", &png);
+        let (pipeline_status, _) = server.post("/process?pipeline=swirl", &png);
+        let (format_status, _) = server.post("/process?format=psd", &png);
+        let (broken_status, _) = server.post("/process", b"not an image");
+        let (method_status, _) = server.send("GET /process HTTP/1.1\r\n\r\n", b"");
+        let (path_status, _) = server.post("/resize", &png);
+        let (tiny_status, _) = server.post("/process?pipeline=resize%3A16384%2C16384", &tiny);
+        let (small_status, small_error) = server.post("/process?pipeline=blur%3Agaussian", &tiny);
+        let (crop_status, crop_error) = server.post("/process?pipeline=crop%3A2%2C2%2C4%2C4", &png);
+        let (too_large_status, _) = server.send(
+            "POST /process HTTP/1.1\r\nContent-Length: 999999999999\r\n\r\n",
+            b"",
+        );
+        drop(server);
+
+        assert_eq!(process_status, 200);
+        let (processed, _): (Images<u8>, _) =
+            decode_image(&processed, None, &ReadOptions::new()).unwrap();
+        assert_eq!(processed, Images::from_samples(2, 2, 3, vec![120; 12]));
+        assert_eq!(stats_status, 200);
+        let stats = String::from_utf8(stats).unwrap();
+        assert!(stats.starts_with("{\"width\": 4, \"height\": 4, \"channels\": [{\"channel\": \"red\", \"mean\": 120, \"variance\": 0, "));
+        assert!(stats.contains("{\"channel\": \"alpha\", \"mean\": 255, "));
+        assert_eq!(pipeline_status, 400);
+        assert_eq!(format_status, 400);
+        assert_eq!(broken_status, 422);
+        assert_eq!(method_status, 405);
+        assert_eq!(path_status, 404);
+        assert_eq!(tiny_status, 413);
+        assert_eq!(small_status, 422);
+        assert!(String::from_utf8(small_error)
+            .unwrap()
+            .contains("1x1 is too small"));
+        assert_eq!(crop_status, 422);
+        assert!(String::from_utf8(crop_error)
+            .unwrap()
+            .contains("outside of the 4x4 image"));
+        assert_eq!(too_large_status, 413);
+    }
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::temp_path;
+use image_processor::core::image::Images;
+
+/// Image with a different value in every sample, so misplaced tiles show up
+fn common_setup_gradient(width: u32, height: u32) -> Images<u8> {
+    let samples = (0..width * height * 3)
+        .map(|index| ((index * 7) % 251) as u8)
+        .collect();
+
+    Images::from_samples(width, height, 3, samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use image_processor::{
+        core::{
+            image::Images,
+            operations::Operation,
+            tiling::{apply_tiled, process_tiled, TileOptions},
+        },
+        filters::{
+            blur::{Blur, SmoothingKernelChoices},
+            gamma_correction::GammaCorrection,
+            gray_scale::{GrayScale, GrayScaleAlgorithms},
+            sharpen::{Sharpen, SharpeningKernelChoices},
+        },
+        transformations::resize::ResizeNearestNeighbour,
+        utils::image_io::{image_reader, image_writer, PngRowReader, PngRowWriter},
+    };
+
+    use super::*;
+
+    fn common_setup_operations() -> Vec<Box<dyn Operation<u8>>> {
+        vec![
+            Box::new(Blur::new(SmoothingKernelChoices::Gaussian)),
+            Box::new(GammaCorrection::new(0.8)),
+            Box::new(Sharpen::new(SharpeningKernelChoices::Basic)),
+        ]
+    }
+
+    #[test]
+    fn tiled_matches_whole_image_test() {
+        let img = common_setup_gradient(23, 17);
+        let operations = common_setup_operations();
+
+        let mut expected = img.clone();
+        for operation in operations.iter() {
+            expected = operation.apply(&expected);
+        }
+        assert_eq!(expected.get_width(), 19);
+
+        for (tile_width, tile_height) in [(1, 1), (4, 5), (7, 3), (64, 64)] {
+            let tile_options = TileOptions::new().with_tile_size(tile_width, tile_height);
+            let tiled = apply_tiled(&img, &operations, &tile_options).unwrap();
+            assert_eq!(tiled, expected, "{}x{} tiles", tile_width, tile_height);
+        }
+    }
+
+    #[test]
+    fn tiled_channel_change_test() {
+        let img = common_setup_gradient(9, 6);
+        let operations: Vec<Box<dyn Operation<u8>>> =
+            vec![Box::new(GrayScale::new(GrayScaleAlgorithms::Luminosity))];
+
+        let tiled = apply_tiled(&img, &operations, &TileOptions::new().with_tile_size(4, 4));
+
+        assert_eq!(tiled.unwrap(), operations[0].apply(&img));
+    }
+
+    #[test]
+    fn tiled_rejects_geometric_operations_test() {
+        let img = common_setup_gradient(8, 8);
+        let operations: Vec<Box<dyn Operation<u8>>> =
+            vec![Box::new(ResizeNearestNeighbour::new(4, 4))];
+
+        assert!(apply_tiled(&img, &operations, &TileOptions::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn tiled_png_streaming_test() {
+        let img = common_setup_gradient(31, 12);
+        let operations = common_setup_operations();
+        let input_path = temp_path("tiled_input.png");
+        let input_path = input_path.to_str().unwrap();
+        let output_path = temp_path("tiled_output.png");
+        let output_path = output_path.to_str().unwrap();
+        image_writer(input_path, &img).unwrap();
+
+        let mut source = PngRowReader::open(input_path).unwrap();
+        let mut sink = PngRowWriter::new(output_path);
+        let tile_options = TileOptions::new().with_tile_size(8, 4);
+        process_tiled(&mut source, &mut sink, &operations, &tile_options).unwrap();
+        let streamed: Images<u8> = image_reader(output_path).await.unwrap();
+        std::fs::remove_file(input_path).unwrap();
+        std::fs::remove_file(output_path).unwrap();
+
+        assert_eq!(
+            streamed,
+            apply_tiled(&img, &operations, &tile_options).unwrap()
+        );
+    }
+}
@@ -42,7 +42,9 @@ mod tests {
         transformations::{
             crop::Crop,
             resize::{ResizeBilinearInterpolation, ResizeNearestNeighbour},
-            rotate::{Flip90Left, Flip90Right, FlipHorizontal, FlipVertical},
+            rotate::{
+                orientation_operations, Flip90Left, Flip90Right, FlipHorizontal, FlipVertical,
+            },
         },
     };
 
@@ -57,7 +59,7 @@ mod tests {
         let cropped_img = Crop::new((1, 1), 2, 2).apply(&img);
 
         // Assert the result
-        let expected_img = Images::new(
+        let expected_img: Images<u8> = Images::new(
             2,
             2,
             3,
@@ -101,7 +103,7 @@ mod tests {
     #[test]
     fn resize_bilinear_interpolation_test() {
         // Create a sample image
-        let img = common_setup_complex();
+        let img: Images<u8> = common_setup_complex();
 
         // Apply cropping operation
         let resized_img = ResizeBilinearInterpolation::new(2, 2).apply(&img);
@@ -212,4 +214,75 @@ mod tests {
         );
         assert_eq!(resized_img, expected_img);
     }
+
+    #[test]
+    fn resize_bilinear_interpolation_linear_light_test() {
+        let img: Images<u8> = Images::new(
+            2,
+            1,
+            4,
+            vec![Pixels::new(0, 0, 0, 0), Pixels::new(255, 255, 255, 255)],
+        );
+
+        let resized_img = ResizeBilinearInterpolation::new(3, 1)
+            .with_linear_light(true)
+            .apply(&img);
+
+        let expected_img = Images::new(
+            3,
+            1,
+            4,
+            vec![
+                Pixels::new(0, 0, 0, 0),
+                Pixels::new(213, 213, 213, 170),
+                Pixels::new(255, 255, 255, 255),
+            ],
+        );
+        assert_eq!(resized_img, expected_img);
+    }
+
+    #[test]
+    fn rotate_non_square_test() {
+        // 0 1 2
+        // 3 4 5
+        let img: Images<u8> = Images::from_samples(3, 2, 1, vec![0, 1, 2, 3, 4, 5]);
+
+        let left_img = Flip90Left::new().apply(&img);
+        let right_img = Flip90Right::new().apply(&img);
+        assert_eq!(
+            left_img,
+            Images::from_samples(2, 3, 1, vec![3, 0, 4, 1, 5, 2])
+        );
+        assert_eq!(
+            right_img,
+            Images::from_samples(2, 3, 1, vec![0, 3, 1, 4, 2, 5])
+        );
+    }
+
+    #[test]
+    fn orientation_operations_test() {
+        // Upright image:
+        // 0 1 2
+        // 3 4 5
+        let upright: Images<u8> = Images::from_samples(3, 2, 1, vec![0, 1, 2, 3, 4, 5]);
+        // The same image as stored for each EXIF orientation
+        let stored: Vec<Images<u8>> = vec![
+            Images::from_samples(3, 2, 1, vec![0, 1, 2, 3, 4, 5]),
+            Images::from_samples(3, 2, 1, vec![2, 1, 0, 5, 4, 3]),
+            Images::from_samples(3, 2, 1, vec![5, 4, 3, 2, 1, 0]),
+            Images::from_samples(3, 2, 1, vec![3, 4, 5, 0, 1, 2]),
+            Images::from_samples(2, 3, 1, vec![0, 3, 1, 4, 2, 5]),
+            Images::from_samples(2, 3, 1, vec![2, 5, 1, 4, 0, 3]),
+            Images::from_samples(2, 3, 1, vec![5, 2, 4, 1, 3, 0]),
+            Images::from_samples(2, 3, 1, vec![3, 0, 4, 1, 5, 2]),
+        ];
+
+        for (index, stored_img) in stored.into_iter().enumerate() {
+            let mut img = stored_img;
+            for operation in orientation_operations((index + 1) as u16) {
+                img = operation.apply(&img);
+            }
+            assert_eq!(img, upright, "orientation {}", index + 1);
+        }
+    }
 }
@@ -1,22 +1,1243 @@
+mod common;
+
+use common::temp_path;
+use image_processor::core::image::Images;
+
 const TOL: f64 = 0.001;
 
+/// Little endian EXIF data holding only the orientation tag
+fn exif_with_orientation(orientation: u16) -> Vec<u8> {
+    let mut exif = b"II*\0".to_vec();
+    exif.extend(8_u32.to_le_bytes());
+    exif.extend(1_u16.to_le_bytes());
+    exif.extend(0x0112_u16.to_le_bytes());
+    exif.extend(3_u16.to_le_bytes());
+    exif.extend(1_u32.to_le_bytes());
+    exif.extend(orientation.to_le_bytes());
+    exif.extend([0, 0, 0, 0, 0, 0]);
+
+    exif
+}
+
+/// Panics on images starting with a black sample, like an operation with a bug would
+struct PanicOnBlack;
+
+impl image_processor::core::operations::Operation<u8> for PanicOnBlack {
+    fn apply(&self, old_image: &Images<u8>) -> Images<u8> {
+        assert_ne!(old_image.get_samples()[0], 0, "black image");
+        old_image.clone()
+    }
+}
+
+/// Keeps the messages of every log event, as the library logs nothing without a logger
+struct CapturingLogger {
+    messages: std::sync::Mutex<Vec<String>>,
+}
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.messages
+            .lock()
+            .unwrap()
+            .push(format!("{} {}", record.level(), record.args()));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger {
+    messages: std::sync::Mutex::new(Vec::new()),
+};
+
+/// Image with texture in every channel, so SSIM sees structure to compare
+fn common_setup_texture(width: u32, height: u32) -> Images<u8> {
+    let samples = (0..width * height * 3)
+        .map(|index| {
+            let pixel = index / 3;
+            let (x, y) = (pixel % width, pixel / width);
+            (40 + (x * 13 + y * 7 + (x * y) % 17 + index % 3 * 29) % 180) as u8
+        })
+        .collect();
+
+    Images::from_samples(width, height, 3, samples)
+}
+
 #[cfg(test)]
 mod tests {
     use image_processor::{
-        core::{image::Images, pixel::Pixels},
-        utils::color_space_converter::rgba_to_hsv,
+        core::{
+            animation::{Animation, AnimationFrame, Disposal},
+            cache::{fnv1a, pipeline_fingerprint, IncrementalMode},
+            image::Images,
+            mask::{MaskRegion, Masked},
+            operations::{process_images, Operation, ProcessOptions},
+            pixel::Pixels,
+            progress::{CancellationToken, ProgressEvent, ProgressHandler},
+            watch::{watch_folder, FileState, FolderWatcher, WatchOptions},
+        },
+        filters::blur::{Blur, SmoothingKernelChoices},
+        transformations::resize::ResizeNearestNeighbour,
+        utils::chart::{ChartOptions, HistogramChart},
+        utils::color_space_converter::{
+            convert_from_color_space, convert_to_color_space, hsv_to_rgba, linear_to_srgb,
+            pixel_to_color, rgba_to_hsv, srgb_to_linear, ColorImage, ColorSpace, Hsl, Hsv, Lab,
+            Luv, Oklab, Xyz, YCbCr,
+        },
+        utils::color_space_converter::{
+            ConvertToWorkingSpace, IccProfile, ToneCurve, WorkingSpace,
+        },
+        utils::directory::{find_images, glob_match, DirectoryOptions},
+        utils::image_io::{
+            animation_reader, animation_writer, async_image_reader, decode_image, encode_image,
+            image_reader, image_reader_with_options, image_to_writer, image_writer,
+            image_writer_with_options, OutputFormat, OutputOptions, ReadOptions,
+        },
+        utils::metadata::{read_metadata, ImageMetadata},
+        utils::statistics::{
+            compute_histogram, compute_mean, compute_ms_ssim, compute_mse, compute_psnr,
+            compute_ssim, compute_ssim_map, compute_variance, ImageStatistics, StatisticsFormat,
+        },
     };
 
     use super::*;
 
+    fn common_setup_colors() -> Images<u8> {
+        Images::new(
+            3,
+            2,
+            4,
+            vec![
+                Pixels::new(10, 20, 30, 255),
+                Pixels::new(255, 0, 0, 128),
+                Pixels::new(0, 255, 0, 0),
+                Pixels::new(0, 0, 255, 255),
+                Pixels::new(255, 255, 255, 255),
+                Pixels::new(0, 0, 0, 255),
+            ],
+        )
+    }
+
+    fn round_trip<C: ColorSpace>() {
+        let rgba_image = common_setup_colors();
+
+        let converted: ColorImage<C> = convert_to_color_space(&rgba_image);
+        let round_trip_image: Images<u8> = convert_from_color_space(&converted);
+        assert_eq!(round_trip_image, rgba_image);
+    }
+
     #[test]
     fn rgba_to_hsv_test() {
-        let rgba_image = Images::new(1, 1, 3, vec![Pixels::new(10, 20, 30, 255)]);
+        let rgba_image: Images<u8> = Images::new(1, 1, 3, vec![Pixels::new(10, 20, 30, 255)]);
 
-        let computed_hsv_image = rgba_to_hsv(rgba_image);
+        let computed_hsv_image = rgba_to_hsv(&rgba_image);
         let expected_hsv_image = [(210.0, 0.67, 0.12)];
-        assert!(computed_hsv_image[0].0 - expected_hsv_image[0].0 < TOL);
-        assert!(computed_hsv_image[0].1 - expected_hsv_image[0].1 < TOL);
-        assert!(computed_hsv_image[0].2 - expected_hsv_image[0].2 < TOL);
+        let computed_hsv = computed_hsv_image.get_colors()[0];
+        assert!(computed_hsv.hue - expected_hsv_image[0].0 < TOL);
+        assert!(computed_hsv.saturation - expected_hsv_image[0].1 < TOL);
+        assert!(computed_hsv.value - expected_hsv_image[0].2 < TOL);
+    }
+
+    #[test]
+    fn hsv_round_trip_test() {
+        let rgba_image = common_setup_colors();
+
+        let round_trip_image: Images<u8> = hsv_to_rgba(&rgba_to_hsv(&rgba_image));
+        assert_eq!(round_trip_image, rgba_image);
+    }
+
+    #[test]
+    fn color_space_round_trip_test() {
+        round_trip::<Hsl>();
+        round_trip::<YCbCr>();
+        round_trip::<Xyz>();
+        round_trip::<Lab>();
+        round_trip::<Luv>();
+        round_trip::<Oklab>();
+    }
+
+    #[test]
+    fn white_reference_values_test() {
+        let white: Pixels<u8> = Pixels::new(255, 255, 255, 255);
+
+        let lab: Lab = pixel_to_color(&white);
+        assert!((lab.l - 100.0).abs() < TOL);
+        assert!(lab.a.abs() < TOL);
+        assert!(lab.b.abs() < TOL);
+
+        let oklab: Oklab = pixel_to_color(&white);
+        assert!((oklab.l - 1.0).abs() < TOL);
+
+        let ycbcr: YCbCr = pixel_to_color(&white);
+        assert!((ycbcr.y - 1.0).abs() < TOL);
+        assert!(ycbcr.cb.abs() < TOL);
+        assert!(ycbcr.cr.abs() < TOL);
+
+        let hsl: Hsl = pixel_to_color(&white);
+        assert!((hsl.lightness - 1.0).abs() < TOL);
+        assert!(hsl.saturation.abs() < TOL);
+
+        let hsv: Hsv = pixel_to_color(&Pixels::<u8>::new(255, 0, 0, 255));
+        assert!(hsv.hue.abs() < TOL);
+        assert!((hsv.saturation - 1.0).abs() < TOL);
+    }
+
+    #[test]
+    fn srgb_linearization_round_trip_test() {
+        assert!(srgb_to_linear(0.0).abs() < TOL);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < TOL);
+        assert!((srgb_to_linear(0.5) - 0.214).abs() < TOL);
+
+        for value in 0..=255 {
+            let encoded = value as f64 / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(encoded)) - encoded).abs() < TOL);
+        }
+    }
+
+    #[tokio::test]
+    async fn image_io_u16_round_trip_test() {
+        let rgba_image: Images<u16> = Images::new(
+            2,
+            1,
+            4,
+            vec![
+                Pixels::new(1, 256, 65535, 65535),
+                Pixels::new(40000, 0, 12345, 30000),
+            ],
+        );
+        let filepath = temp_path("u16_round_trip.png");
+        let filepath = filepath.to_str().unwrap();
+
+        image_writer(filepath, &rgba_image).unwrap();
+        let read_image: Images<u16> = image_reader(filepath).await.unwrap();
+        std::fs::remove_file(filepath).unwrap();
+
+        assert_eq!(read_image.get_image(), rgba_image.get_image());
+    }
+
+    #[tokio::test]
+    async fn image_io_gray_round_trip_test() {
+        let gray_image: Images<u8> = Images::from_samples(3, 1, 1, vec![0, 128, 255]);
+        let filepath = temp_path("gray_round_trip.png");
+        let filepath = filepath.to_str().unwrap();
+
+        image_writer(filepath, &gray_image).unwrap();
+        let written_color = image::open(filepath).unwrap().color();
+        let read_image: Images<u8> = image_reader(filepath).await.unwrap();
+        std::fs::remove_file(filepath).unwrap();
+
+        assert_eq!(written_color, image::ColorType::L8);
+        assert_eq!(read_image, gray_image);
+    }
+
+    #[tokio::test]
+    async fn image_io_output_format_test() {
+        let rgba_image: Images<u16> = Images::new(1, 1, 4, vec![Pixels::new(65535, 0, 0, 30000)]);
+        let jpeg_path = temp_path("output_format.jpg");
+        let jpeg_path = jpeg_path.to_str().unwrap();
+        let bmp_path = temp_path("output_format.out");
+        let bmp_path = bmp_path.to_str().unwrap();
+
+        let no_metadata = ImageMetadata::default();
+        let jpeg_options = OutputOptions::new().with_jpeg_quality(100);
+        image_writer_with_options(jpeg_path, &rgba_image, &jpeg_options, &no_metadata).unwrap();
+        let bmp_options = OutputOptions::new().with_format(OutputFormat::Bmp);
+        image_writer_with_options(bmp_path, &rgba_image, &bmp_options, &no_metadata).unwrap();
+
+        let jpeg_image: Images<u8> = image_reader(jpeg_path).await.unwrap();
+        let bmp_bytes = std::fs::read(bmp_path).unwrap();
+        std::fs::remove_file(jpeg_path).unwrap();
+        std::fs::remove_file(bmp_path).unwrap();
+
+        // JPEG has no alpha and only 8 bit samples
+        assert_eq!(jpeg_image.get_channels(), 3);
+        assert!(jpeg_image.get_pixel_at(0, 0).unwrap().get_red() > 250);
+        assert_eq!(
+            image::guess_format(&bmp_bytes).unwrap(),
+            image::ImageFormat::Bmp
+        );
+    }
+
+    #[test]
+    fn output_extension_test() {
+        let source = std::path::Path::new("assets/lenna.jpeg");
+
+        assert_eq!(OutputOptions::new().extension_for(source), "jpeg");
+        assert_eq!(
+            OutputOptions::new()
+                .with_format(OutputFormat::WebP)
+                .extension_for(source),
+            "webp"
+        );
+    }
+
+    #[tokio::test]
+    async fn image_io_metadata_round_trip_test() {
+        let image: Images<u8> =
+            Images::from_samples(2, 1, 4, vec![10, 20, 30, 255, 40, 50, 60, 128]);
+        let metadata = ImageMetadata::new(
+            Some(exif_with_orientation(1)),
+            Some(vec![7; 70000]),
+            Some(b"<x:xmpmeta/>".to_vec()),
+        );
+        let keep_options = OutputOptions::new().with_keep_metadata(true);
+
+        for extension in ["png", "jpg", "webp"] {
+            let filepath = temp_path(&format!("metadata.{}", extension));
+            let filepath = filepath.to_str().unwrap();
+
+            image_writer_with_options(filepath, &image, &keep_options, &metadata).unwrap();
+            let kept_metadata = read_metadata(&std::fs::read(filepath).unwrap());
+            let decoded = image::open(filepath).unwrap();
+            image_writer_with_options(filepath, &image, &OutputOptions::new(), &metadata).unwrap();
+            let stripped_metadata = read_metadata(&std::fs::read(filepath).unwrap());
+            let strip_all_options = OutputOptions::new().with_embed_icc_profile(false);
+            image_writer_with_options(filepath, &image, &strip_all_options, &metadata).unwrap();
+            let stripped_all_metadata = read_metadata(&std::fs::read(filepath).unwrap());
+            std::fs::remove_file(filepath).unwrap();
+
+            assert_eq!(kept_metadata, metadata, "{}", extension);
+            assert_eq!(decoded.width(), 2, "{}", extension);
+            assert_eq!(
+                stripped_metadata,
+                metadata.icc_profile_only(),
+                "{}",
+                extension
+            );
+            assert!(stripped_all_metadata.is_empty(), "{}", extension);
+        }
+    }
+
+    #[tokio::test]
+    async fn image_reader_auto_orient_test() {
+        // 3x2 image stored turned a quarter counterclockwise (EXIF orientation 6)
+        let stored: Images<u8> = Images::from_samples(3, 2, 1, vec![0, 50, 100, 150, 200, 250]);
+        let metadata = ImageMetadata::new(Some(exif_with_orientation(6)), None, None);
+        let filepath = temp_path("auto_orient.png");
+        let filepath = filepath.to_str().unwrap();
+        let keep_options = OutputOptions::new().with_keep_metadata(true);
+        image_writer_with_options(filepath, &stored, &keep_options, &metadata).unwrap();
+
+        let (as_stored, stored_metadata): (Images<u8>, _) =
+            image_reader_with_options(filepath, &ReadOptions::new())
+                .await
+                .unwrap();
+        let (upright, upright_metadata): (Images<u8>, _) =
+            image_reader_with_options(filepath, &ReadOptions::new().with_auto_orient(true))
+                .await
+                .unwrap();
+        std::fs::remove_file(filepath).unwrap();
+
+        assert_eq!(as_stored, stored);
+        assert_eq!(stored_metadata.get_orientation(), Some(6));
+        assert_eq!(
+            upright,
+            Images::from_samples(2, 3, 1, vec![150, 0, 200, 50, 250, 100])
+        );
+        assert_eq!(upright_metadata.get_orientation(), Some(1));
+    }
+
+    fn adobe_rgb_profile() -> IccProfile {
+        let gamma = ToneCurve::Gamma(563.0 / 256.0);
+        IccProfile::new(
+            "Adobe RGB (1998)",
+            Some([
+                [0.6097, 0.3111, 0.0195],
+                [0.2053, 0.6257, 0.0609],
+                [0.1492, 0.0632, 0.7446],
+            ]),
+            [gamma.clone(), gamma.clone(), gamma],
+        )
+    }
+
+    #[test]
+    fn icc_profile_round_trip_test() {
+        for profile in [
+            IccProfile::srgb(),
+            IccProfile::linear_srgb(),
+            adobe_rgb_profile(),
+        ] {
+            let parsed = IccProfile::parse(&profile.to_bytes()).unwrap();
+
+            assert_eq!(parsed.get_description(), profile.get_description());
+            for value in [0.0, 0.02, 0.3, 0.75, 1.0] {
+                let pixel = [value, value * 0.5, 1.0 - value];
+                let image: Images<f32> = Images::from_samples(
+                    1,
+                    1,
+                    3,
+                    pixel.iter().map(|value| *value as f32).collect(),
+                );
+                let expected =
+                    ConvertToWorkingSpace::new(profile.clone(), WorkingSpace::Srgb).apply(&image);
+                let computed =
+                    ConvertToWorkingSpace::new(parsed.clone(), WorkingSpace::Srgb).apply(&image);
+                for (lhs, rhs) in expected.get_samples().iter().zip(computed.get_samples()) {
+                    assert!((lhs - rhs).abs() < 0.001);
+                }
+            }
+        }
+        assert!(IccProfile::parse(b"not a profile").is_err());
+
+        let mut oversized_table = IccProfile::srgb().to_bytes();
+        oversized_table[128..132].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(IccProfile::parse(&oversized_table).is_err());
+    }
+
+    #[test]
+    fn working_space_conversion_test() {
+        let image: Images<u8> =
+            Images::from_samples(2, 1, 4, vec![128, 128, 128, 200, 255, 0, 0, 255]);
+
+        let srgb_image =
+            ConvertToWorkingSpace::new(IccProfile::srgb(), WorkingSpace::Srgb).apply(&image);
+        let linear_image =
+            ConvertToWorkingSpace::new(IccProfile::srgb(), WorkingSpace::LinearSrgb).apply(&image);
+        let adobe_image =
+            ConvertToWorkingSpace::new(adobe_rgb_profile(), WorkingSpace::Srgb).apply(&image);
+
+        assert_eq!(srgb_image, image);
+        assert_eq!(&linear_image.get_samples()[0..4], &[55, 55, 55, 200]);
+        // Neutral gray stays neutral, saturated Adobe RGB red is clipped to sRGB red
+        assert_eq!(
+            adobe_image.get_samples(),
+            &[129, 129, 129, 200, 255, 0, 0, 255]
+        );
+    }
+
+    #[tokio::test]
+    async fn image_reader_working_space_test() {
+        let image: Images<u8> = Images::from_samples(1, 1, 3, vec![128, 128, 128]);
+        let metadata = ImageMetadata::new(None, Some(adobe_rgb_profile().to_bytes()), None);
+        let filepath = temp_path("working_space.png");
+        let filepath = filepath.to_str().unwrap();
+        image_writer_with_options(filepath, &image, &OutputOptions::new(), &metadata).unwrap();
+
+        let read_options = ReadOptions::new().with_working_space(Some(WorkingSpace::LinearSrgb));
+        let (linear_image, linear_metadata): (Images<u8>, _) =
+            image_reader_with_options(filepath, &read_options)
+                .await
+                .unwrap();
+        std::fs::remove_file(filepath).unwrap();
+
+        assert_eq!(linear_image.get_samples(), &[56, 56, 56]);
+        assert_eq!(
+            linear_metadata.get_icc_profile(),
+            Some(IccProfile::linear_srgb().to_bytes().as_slice())
+        );
+    }
+
+    fn common_setup_animation() -> Animation<u8> {
+        let red: Images<u8> = Images::from_samples(2, 2, 4, [255, 0, 0, 255].repeat(4));
+        let blue: Images<u8> = Images::from_samples(2, 2, 4, [0, 0, 255, 255].repeat(4));
+
+        Animation::new(
+            vec![
+                AnimationFrame::new(red, 100, Disposal::None),
+                AnimationFrame::new(blue, 250, Disposal::Background),
+            ],
+            0,
+        )
+    }
+
+    #[tokio::test]
+    async fn animation_round_trip_test() {
+        let animation = common_setup_animation();
+        for extension in ["gif", "png"] {
+            let filepath = temp_path(&format!("animation.{}", extension));
+            let filepath = filepath.to_str().unwrap();
+
+            animation_writer(filepath, &animation, &OutputOptions::new()).unwrap();
+            let read_animation: Animation<u8> = animation_reader(filepath).await.unwrap();
+            std::fs::remove_file(filepath).unwrap();
+
+            let frames = read_animation.get_frames();
+            assert_eq!(frames.len(), 2, "{}", extension);
+            assert_eq!(frames[0].get_delay_ms(), 100, "{}", extension);
+            assert_eq!(frames[1].get_delay_ms(), 250, "{}", extension);
+            for (frame, expected) in frames.iter().zip(animation.get_frames()) {
+                assert_eq!(frame.get_image(), expected.get_image(), "{}", extension);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn animation_still_image_test() {
+        let image: Images<u8> = Images::from_samples(1, 1, 3, vec![10, 20, 30]);
+        let filepath = temp_path("animation_still.png");
+        let filepath = filepath.to_str().unwrap();
+        image_writer(filepath, &image).unwrap();
+
+        let animation: Animation<u8> = animation_reader(filepath).await.unwrap();
+        std::fs::remove_file(filepath).unwrap();
+
+        assert_eq!(animation.get_frames().len(), 1);
+        assert_eq!(animation.get_frames()[0].get_image(), &image);
+    }
+
+    #[test]
+    fn animation_operations_test() {
+        let animation = common_setup_animation();
+        let operations: Vec<Box<dyn Operation<u8>>> =
+            vec![Box::new(ResizeNearestNeighbour::new(4, 3))];
+
+        let resized = animation.apply_operations(&operations);
+
+        assert_eq!(resized.get_dimensions(), Some((4, 3)));
+        assert_eq!(resized.get_frames()[1].get_delay_ms(), 250);
+        assert_eq!(resized.get_frames()[1].get_disposal(), Disposal::Background);
+        assert_eq!(
+            &resized.get_frames()[1].get_image().get_samples()[0..4],
+            &[0, 0, 255, 255]
+        );
+
+        let webp_path = temp_path("animation.webp");
+        assert!(
+            animation_writer(webp_path.to_str().unwrap(), &resized, &OutputOptions::new()).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn image_io_in_memory_test() {
+        let image: Images<u8> = Images::from_samples(2, 1, 3, vec![10, 20, 30, 40, 50, 60]);
+        let bmp_options = OutputOptions::new().with_format(OutputFormat::Bmp);
+
+        let encoded = encode_image(&image, &bmp_options, &ImageMetadata::default()).unwrap();
+        assert_eq!(&encoded[0..2], b"BM");
+        let mut written = Vec::new();
+        image_to_writer(
+            &mut written,
+            &image,
+            &bmp_options,
+            &ImageMetadata::default(),
+        )
+        .unwrap();
+        assert_eq!(written, encoded);
+
+        let (sniffed, _): (Images<u8>, _) =
+            async_image_reader(encoded.as_slice(), None, &ReadOptions::new())
+                .await
+                .unwrap();
+        assert_eq!(sniffed, image);
+        let (specified, _): (Images<u8>, _) =
+            decode_image(&encoded, Some(image::ImageFormat::Bmp), &ReadOptions::new()).unwrap();
+        assert_eq!(specified, image);
+
+        assert!(
+            decode_image::<u8>(&encoded, Some(image::ImageFormat::Png), &ReadOptions::new())
+                .is_err()
+        );
+        assert!(encode_image(&image, &OutputOptions::new(), &ImageMetadata::default()).is_err());
+    }
+
+    #[test]
+    fn glob_match_test() {
+        assert!(glob_match("*.png", "lenna.png"));
+        assert!(!glob_match("*.png", "maps/lenna.png"));
+        assert!(glob_match("**/*.png", "lenna.png"));
+        assert!(glob_match("**/*.png", "maps/2024/lenna.png"));
+        assert!(glob_match(
+            "maps/*/tile_??.{png,jpg}",
+            "maps/2024/tile_01.jpg"
+        ));
+        assert!(!glob_match(
+            "maps/*/tile_??.{png,jpg}",
+            "maps/2024/tile_001.jpg"
+        ));
+        assert!(!glob_match("maps/*.png", "maps/2024/lenna.png"));
+
+        // Each split of the stars is tried once, so this fails fast instead of backtracking
+        assert!(!glob_match(&"*a".repeat(30), &"a".repeat(29)));
+        assert!(!glob_match(
+            &format!("{}*.jpg", "**/".repeat(20)),
+            &format!("{}lenna.png", "a/".repeat(40))
+        ));
+    }
+
+    #[tokio::test]
+    async fn process_images_recursive_test() {
+        let dir = temp_path("recursive_in");
+        let dir_out = temp_path("recursive_out");
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_dir_all(&dir_out);
+        std::fs::create_dir_all(dir.join("maps/old")).unwrap();
+        let image: Images<u8> = Images::from_samples(2, 2, 3, vec![90; 12]);
+        for name in [
+            "top.png",
+            "maps/map.png",
+            "maps/old/map.png",
+            "maps/skip.png",
+        ] {
+            image_writer(dir.join(name).to_str().unwrap(), &image).unwrap();
+        }
+        std::fs::write(dir.join("maps/README.md"), "not an image").unwrap();
+
+        let directory_options = DirectoryOptions::new()
+            .with_recursive(true)
+            .with_exclude("skip.*");
+        let (found, skipped) = find_images(dir.to_str().unwrap(), &directory_options)
+            .await
+            .unwrap();
+        assert_eq!(
+            found,
+            ["maps/map.png", "maps/old/map.png", "top.png"].map(std::path::PathBuf::from)
+        );
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].get_path(), &dir.join("maps/README.md"));
+        let (flat, skipped) = find_images(dir.to_str().unwrap(), &DirectoryOptions::new())
+            .await
+            .unwrap();
+        assert_eq!(flat, [std::path::PathBuf::from("top.png")]);
+        assert_eq!(skipped[0].get_reason(), "folder");
+
+        let operations: Vec<Box<dyn Operation<u8>>> = Vec::new();
+        let options = ProcessOptions::new()
+            .with_output_options(OutputOptions::new().with_format(OutputFormat::Bmp))
+            .with_directory_options(directory_options.with_include("**/map.png"));
+        process_images(
+            true,
+            dir.to_str(),
+            &(dir_out.to_str().unwrap().to_string() + "/"),
+            None,
+            std::sync::Arc::new(operations),
+            false,
+            &options,
+        )
+        .await
+        .unwrap();
+        let written = [
+            dir_out.join("maps/map.bmp").exists(),
+            dir_out.join("maps/old/map.bmp").exists(),
+            dir_out.join("top.bmp").exists(),
+        ];
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&dir_out).unwrap();
+
+        assert_eq!(written, [true, true, false]);
+    }
+
+    #[tokio::test]
+    async fn process_images_panic_test() {
+        let dir = temp_path("panic_in");
+        let dir_out = temp_path("panic_out");
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_dir_all(&dir_out);
+        std::fs::create_dir_all(&dir).unwrap();
+        for index in 0..3_u8 {
+            let image: Images<u8> = Images::from_samples(2, 2, 3, vec![index * 40; 12]);
+            image_writer(dir.join(format!("{}.png", index)).to_str().unwrap(), &image).unwrap();
+        }
+
+        let operations: Vec<Box<dyn Operation<u8>>> = vec![Box::new(PanicOnBlack)];
+        let operations = std::sync::Arc::new(operations);
+        let dir_out_name = dir_out.to_str().unwrap().to_string() + "/";
+        let options = ProcessOptions::new().with_continue_on_error(true);
+        let report = process_images(
+            true,
+            dir.to_str(),
+            &dir_out_name,
+            None,
+            operations.clone(),
+            false,
+            &options,
+        )
+        .await
+        .unwrap();
+        let stopped = process_images(
+            true,
+            dir.to_str(),
+            &dir_out_name,
+            None,
+            operations,
+            false,
+            &options.with_continue_on_error(false),
+        )
+        .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&dir_out).unwrap();
+
+        assert_eq!(report.get_processed().len(), 2);
+        assert_eq!(report.get_failed().len(), 1);
+        assert_eq!(report.get_failed()[0].get_input(), &dir.join("0.png"));
+        assert!(stopped.is_err());
+    }
+
+    #[tokio::test]
+    async fn process_images_concurrency_test() {
+        let dir = temp_path("concurrent_in");
+        let dir_out = temp_path("concurrent_out");
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_dir_all(&dir_out);
+        std::fs::create_dir_all(&dir).unwrap();
+        for index in 0..6_u8 {
+            let image: Images<u8> = Images::from_samples(4, 4, 3, vec![index * 40; 48]);
+            image_writer(dir.join(format!("{}.png", index)).to_str().unwrap(), &image).unwrap();
+        }
+
+        let operations: Vec<Box<dyn Operation<u8>>> =
+            vec![Box::new(ResizeNearestNeighbour::new(2, 2))];
+        let operations = std::sync::Arc::new(operations);
+        let options = ProcessOptions::new().with_max_concurrency(2);
+        let dir_out_name = dir_out.to_str().unwrap().to_string() + "/";
+        process_images(
+            true,
+            dir.to_str(),
+            &dir_out_name,
+            None,
+            operations.clone(),
+            false,
+            &options,
+        )
+        .await
+        .unwrap();
+        let mut written = Vec::new();
+        for index in 0..6_u8 {
+            let path = dir_out.join(format!("{}.png", index));
+            let image: Images<u8> = image_reader(path.to_str().unwrap()).await.unwrap();
+            written.push(image);
+        }
+
+        std::fs::write(dir.join("broken.png"), "not a png").unwrap();
+        let broken = process_images(
+            true,
+            dir.to_str(),
+            &dir_out_name,
+            None,
+            operations,
+            false,
+            &options,
+        )
+        .await;
+        let report = process_images::<u8>(
+            true,
+            dir.to_str(),
+            &dir_out_name,
+            None,
+            std::sync::Arc::new(Vec::new()),
+            false,
+            &options.with_continue_on_error(true).with_write_report(true),
+        )
+        .await
+        .unwrap();
+        let report_json = std::fs::read_to_string(dir_out.join("report.json")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&dir_out).unwrap();
+
+        assert!(!report.is_success());
+        assert_eq!(report.get_processed().len(), 6);
+        assert_eq!(report.get_processed()[0].get_input(), &dir.join("0.png"));
+        assert_eq!(report.get_failed().len(), 1);
+        assert_eq!(report.get_failed()[0].get_input(), &dir.join("broken.png"));
+        assert_eq!(report_json, report.to_json());
+        assert!(report_json.contains("\"failed\": [{\"input\": "));
+        for (index, image) in written.iter().enumerate() {
+            assert_eq!(
+                image,
+                &Images::from_samples(2, 2, 3, vec![index as u8 * 40; 12])
+            );
+        }
+        assert!(broken.unwrap_err().to_string().contains("broken.png"));
+    }
+
+    #[tokio::test]
+    async fn process_images_progress_and_cancellation_test() {
+        let dir = temp_path("progress_in");
+        let dir_out = temp_path("progress_out");
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_dir_all(&dir_out);
+        std::fs::create_dir_all(&dir).unwrap();
+        for index in 0..3 {
+            let image: Images<u8> = Images::from_samples(2, 2, 3, vec![7; 12]);
+            image_writer(dir.join(format!("{}.png", index)).to_str().unwrap(), &image).unwrap();
+        }
+        let dir_out_name = dir_out.to_str().unwrap().to_string() + "/";
+        let operations: Vec<Box<dyn Operation<u8>>> = vec![
+            Box::new(ResizeNearestNeighbour::new(1, 1)),
+            Box::new(ResizeNearestNeighbour::new(2, 2)),
+        ];
+        let operations = std::sync::Arc::new(operations);
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let options = ProcessOptions::new()
+            .with_max_concurrency(1)
+            .with_progress(ProgressHandler::from_channel(sender));
+        let report = process_images(
+            true,
+            dir.to_str(),
+            &dir_out_name,
+            None,
+            operations.clone(),
+            false,
+            &options,
+        )
+        .await
+        .unwrap();
+        drop(options);
+        let mut events = Vec::new();
+        while let Some(event) = receiver.recv().await {
+            events.push(event);
+        }
+
+        // Cancels while the first image runs its operations, so the others never start
+        let cancellation = CancellationToken::new();
+        let cancel_on_progress = cancellation.clone();
+        let options = ProcessOptions::new()
+            .with_max_concurrency(1)
+            .with_cancellation(cancellation)
+            .with_progress(ProgressHandler::new(move |event| {
+                if matches!(event, ProgressEvent::Operation { .. }) {
+                    cancel_on_progress.cancel();
+                }
+            }));
+        let cancelled_report = process_images(
+            true,
+            dir.to_str(),
+            &dir_out_name,
+            None,
+            operations,
+            false,
+            &options,
+        )
+        .await
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&dir_out).unwrap();
+
+        assert!(report.is_success());
+        assert_eq!(events.first(), Some(&ProgressEvent::Started { total: 3 }));
+        assert_eq!(
+            events.last(),
+            Some(&ProgressEvent::Finished {
+                done: 3,
+                total: 3,
+                cancelled: false
+            })
+        );
+        let operation_events = events
+            .iter()
+            .filter(|event| matches!(event, ProgressEvent::Operation { .. }))
+            .count();
+        assert_eq!(operation_events, 6);
+        assert!(events.contains(&ProgressEvent::FileFinished {
+            path: dir.join("2.png"),
+            done: 3,
+            total: 3,
+            success: true
+        }));
+
+        assert!(cancelled_report.is_cancelled());
+        assert_eq!(cancelled_report.get_processed().len(), 0);
+        assert_eq!(cancelled_report.get_skipped().len(), 3);
+        assert_eq!(cancelled_report.get_skipped()[0].get_reason(), "cancelled");
+    }
+
+    #[tokio::test]
+    async fn process_images_incremental_test() {
+        let dir = temp_path("incremental_in");
+        let dir_out = temp_path("incremental_out");
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_dir_all(&dir_out);
+        std::fs::create_dir_all(&dir).unwrap();
+        for index in 0..2_u8 {
+            let image: Images<u8> = Images::from_samples(4, 4, 3, vec![index * 60; 48]);
+            image_writer(dir.join(format!("{}.png", index)).to_str().unwrap(), &image).unwrap();
+        }
+        let dir_out_name = dir_out.to_str().unwrap().to_string() + "/";
+        let pipeline = |size: u32| {
+            let operations: Vec<Box<dyn Operation<u8>>> =
+                vec![Box::new(ResizeNearestNeighbour::new(size, size))];
+            std::sync::Arc::new(operations)
+        };
+        let run = |operations, options: ProcessOptions| {
+            let dir_out_name = dir_out_name.clone();
+            let dir = dir.clone();
+            async move {
+                process_images(
+                    true,
+                    dir.to_str(),
+                    &dir_out_name,
+                    None,
+                    operations,
+                    false,
+                    &options,
+                )
+                .await
+                .unwrap()
+            }
+        };
+        let hashing = ProcessOptions::new().with_incremental(IncrementalMode::ContentHash);
+
+        let first = run(pipeline(2), hashing.clone()).await;
+        let repeated = run(pipeline(2), hashing.clone()).await;
+        let changed_pipeline = run(pipeline(3), hashing.clone()).await;
+        let image: Images<u8> = Images::from_samples(4, 4, 3, vec![200; 48]);
+        image_writer(dir.join("1.png").to_str().unwrap(), &image).unwrap();
+        let changed_input = run(pipeline(3), hashing).await;
+        let newer_outputs = run(
+            pipeline(3),
+            ProcessOptions::new().with_incremental(IncrementalMode::SkipNewerOutputs),
+        )
+        .await;
+        let resized: Images<u8> = image_reader(dir_out.join("1.png").to_str().unwrap())
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&dir_out).unwrap();
+
+        assert_eq!(first.get_processed().len(), 2);
+        assert_eq!(repeated.get_processed().len(), 0);
+        assert_eq!(repeated.get_skipped().len(), 2);
+        assert_eq!(repeated.get_skipped()[0].get_reason(), "unchanged");
+        assert!(repeated.is_success());
+        assert_eq!(changed_pipeline.get_processed().len(), 2);
+        assert_eq!(changed_input.get_processed().len(), 1);
+        assert_eq!(
+            changed_input.get_processed()[0].get_input(),
+            &dir.join("1.png")
+        );
+        assert_eq!(newer_outputs.get_processed().len(), 0);
+        assert_eq!(resized, Images::from_samples(3, 3, 3, vec![200; 27]));
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn pipeline_fingerprint_test() {
+        let masked = |mask: Images<u8>| -> Vec<Box<dyn Operation<u8>>> {
+            vec![Box::new(Masked::new(
+                Box::new(Blur::new(SmoothingKernelChoices::Gaussian)),
+                MaskRegion::Image(mask),
+            ))]
+        };
+        let resize = |width: u32| -> Vec<Box<dyn Operation<u8>>> {
+            vec![Box::new(ResizeNearestNeighbour::new(width, 3))]
+        };
+        let mask = Images::from_samples(2, 1, 3, vec![0, 0, 0, 255, 255, 255]);
+
+        let fingerprint = Masked::new(
+            Box::new(Blur::new(SmoothingKernelChoices::Gaussian)),
+            MaskRegion::Image(mask.clone()),
+        )
+        .fingerprint();
+        assert!(fingerprint.starts_with("masked(blur:Gaussian,false,"));
+        assert!(fingerprint.len() < 100);
+        assert_eq!(
+            pipeline_fingerprint(&masked(mask.clone()), ""),
+            pipeline_fingerprint(&masked(mask), "")
+        );
+        assert_ne!(
+            pipeline_fingerprint(&masked(Images::from_samples(1, 1, 3, vec![0; 3])), ""),
+            pipeline_fingerprint(&masked(Images::from_samples(1, 1, 3, vec![255; 3])), "")
+        );
+        assert_ne!(
+            pipeline_fingerprint(&resize(3), ""),
+            pipeline_fingerprint(&resize(4), "")
+        );
+        assert_ne!(
+            pipeline_fingerprint(&resize(3), ""),
+            pipeline_fingerprint(&resize(3), "quality")
+        );
+        assert_eq!(
+            OutputOptions::new().fingerprint(),
+            "output:source,75,Default,Adaptive,false,true"
+        );
+        assert_ne!(
+            OutputOptions::new().fingerprint(),
+            OutputOptions::new().with_jpeg_quality(90).fingerprint()
+        );
+        assert_ne!(
+            ReadOptions::new().fingerprint(),
+            ReadOptions::new().with_auto_orient(true).fingerprint()
+        );
+    }
+
+    #[test]
+    fn folder_watcher_debounce_test() {
+        let start = std::time::Instant::now();
+        let at = |millis: u64| start + std::time::Duration::from_millis(millis);
+        let path = std::path::PathBuf::from("photo.png");
+        let partial = FileState::new(100, None);
+        let complete = FileState::new(250, None);
+        let ready = vec![path.clone()];
+        let mut watcher = FolderWatcher::new(std::time::Duration::from_millis(500));
+
+        assert!(watcher.poll(&[(path.clone(), partial)], at(0)).is_empty());
+        assert!(watcher
+            .poll(&[(path.clone(), complete)], at(400))
+            .is_empty());
+        assert!(watcher
+            .poll(&[(path.clone(), complete)], at(800))
+            .is_empty());
+        assert_eq!(watcher.poll(&[(path.clone(), complete)], at(900)), ready);
+        assert!(watcher
+            .poll(&[(path.clone(), complete)], at(2000))
+            .is_empty());
+        assert!(watcher.poll(&[], at(2100)).is_empty());
+        assert!(watcher
+            .poll(&[(path.clone(), complete)], at(2200))
+            .is_empty());
+        assert_eq!(watcher.poll(&[(path, complete)], at(2700)), ready);
+    }
+
+    #[tokio::test]
+    async fn watch_folder_test() {
+        let dir = temp_path("watch_in");
+        let dir_out = temp_path("watch_out");
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_dir_all(&dir_out);
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir_name = dir.to_str().unwrap().to_string();
+        let dir_out_name = dir_out.to_str().unwrap().to_string() + "/";
+        let operations: Vec<Box<dyn Operation<u8>>> =
+            vec![Box::new(ResizeNearestNeighbour::new(1, 1))];
+        let options = ProcessOptions::new();
+        let cancellation = options.get_cancellation().clone();
+        let watch_options = WatchOptions::new()
+            .with_poll_interval(std::time::Duration::from_millis(10))
+            .with_settle_time(std::time::Duration::from_millis(50));
+        let watching = tokio::spawn(async move {
+            watch_folder(
+                &dir_name,
+                &dir_out_name,
+                std::sync::Arc::new(operations),
+                false,
+                &options,
+                &watch_options,
+            )
+            .await
+            .map_err(|error| error.to_string())
+        });
+
+        let image: Images<u8> = Images::from_samples(2, 2, 3, vec![90; 12]);
+        image_writer(dir.join("dropped.png").to_str().unwrap(), &image).unwrap();
+        let output_path = dir_out.join("dropped.png");
+        let mut waited = 0;
+        while !output_path.exists() && waited < 500 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            waited += 1;
+        }
+        // Gives the writer a moment to finish the file it just created
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        cancellation.cancel();
+        let watched = watching.await.unwrap();
+        let output: Images<u8> = image_reader(output_path.to_str().unwrap()).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&dir_out).unwrap();
+
+        assert!(watched.is_ok());
+        assert_eq!(output, Images::from_samples(1, 1, 3, vec![90; 3]));
+    }
+
+    #[test]
+    fn image_statistics_test() {
+        let samples = [10, 20, 30, 40, 50]
+            .iter()
+            .flat_map(|red| [*red, 7, 0])
+            .collect();
+        let image: Images<u8> = Images::from_samples(5, 1, 3, samples);
+
+        let statistics = ImageStatistics::new_with_percentiles(&image, &[25.0, 90.0]);
+        let red = statistics.get_channel("red").unwrap();
+        let green = &statistics.get_channels()[1];
+
+        assert_eq!(statistics.get_channels().len(), 4);
+        assert_eq!(red.get_mean(), compute_mean(&image).0);
+        assert_eq!(red.get_variance(), compute_variance(&image).0);
+        assert_eq!(red.get_std_dev(), 200.0_f64.sqrt());
+        assert_eq!(
+            (red.get_min(), red.get_max(), red.get_median()),
+            (10.0, 50.0, 30.0)
+        );
+        assert_eq!(red.get_percentiles(), &[(25.0, 20.0), (90.0, 46.0)]);
+        assert_eq!(red.get_percentile(90.0), Some(46.0));
+        assert_eq!(red.get_histogram()[20], 1);
+        assert_eq!(green.get_histogram()[7], 5);
+        assert_eq!(green.get_variance(), 0.0);
+        assert_eq!(statistics.get_channel("alpha").unwrap().get_min(), 255.0);
+
+        let float_image: Images<f32> = Images::from_samples(5, 1, 1, vec![0.5, 0.1, 0.4, 0.2, 0.3]);
+        let float_statistics = ImageStatistics::new_with_percentiles(&float_image, &[25.0, 90.0]);
+        let gray = float_statistics.get_channel("blue").unwrap();
+        assert_eq!((gray.get_min(), gray.get_max()), (0.1_f32 as f64, 0.5));
+        assert_eq!(gray.get_median(), 0.3_f32 as f64);
+        assert_eq!(gray.get_percentile(25.0), Some(0.2_f32 as f64));
+        assert!((gray.get_percentile(90.0).unwrap() - 0.46).abs() < TOL);
+        assert_eq!(
+            float_statistics.get_channel("alpha").unwrap().get_max(),
+            1.0
+        );
+
+        let json = statistics.format(StatisticsFormat::Json);
+        assert!(json.starts_with(
+            "{\"width\": 5, \"height\": 1, \"channels\": [{\"channel\": \"red\", \"mean\": 30, \"variance\": 200, "
+        ));
+        assert!(json.contains(
+            "\"median\": 30, \"percentiles\": {\"25\": 20, \"90\": 46}, \"histogram\": [0, "
+        ));
+        let csv = statistics.format(StatisticsFormat::Csv);
+        let csv_lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            csv_lines[0],
+            "channel,mean,variance,std_dev,min,max,median,p25,p90"
+        );
+        assert!(csv_lines[1].starts_with("red,30,200,14.14"));
+        assert_eq!(csv_lines.len(), 5);
+        let histogram_csv = statistics.format(StatisticsFormat::HistogramCsv);
+        assert_eq!(histogram_csv.lines().count(), 257);
+        assert_eq!(histogram_csv.lines().nth(8), Some("7,0,5,0,0"));
+        let ascii = statistics.format(StatisticsFormat::Ascii);
+        assert!(ascii.contains("red: mean 30.000, variance 200.000"));
+        assert!(!ascii.contains("()"));
+    }
+
+    #[test]
+    fn quality_metrics_test() {
+        let reference = common_setup_texture(64, 48);
+        let brighter = Images::from_samples(
+            64,
+            48,
+            3,
+            reference
+                .get_samples()
+                .iter()
+                .map(|sample| sample + 10)
+                .collect(),
+        );
+        let blurred = Blur::new(SmoothingKernelChoices::BoxBlur).apply(&reference);
+        let blurred = ResizeNearestNeighbour::new(64, 48).apply(&blurred);
+
+        let mse = compute_mse(&reference, &brighter).unwrap();
+        assert_eq!(mse.get_channels(), &[100.0, 100.0, 100.0]);
+        assert_eq!(mse.get_combined(), 100.0);
+        let psnr = compute_psnr(&reference, &brighter).unwrap();
+        assert!((psnr.get_combined() - 28.1308).abs() < 1e-4);
+        assert_eq!(
+            compute_psnr(&reference, &reference).unwrap().get_combined(),
+            f64::INFINITY
+        );
+
+        let same = compute_ssim(&reference, &reference).unwrap();
+        assert!(same
+            .get_channels()
+            .iter()
+            .all(|ssim| (ssim - 1.0).abs() < 1e-12));
+        let shifted = compute_ssim(&reference, &brighter).unwrap().get_combined();
+        let smoothed = compute_ssim(&reference, &blurred).unwrap().get_combined();
+        assert!(shifted > 0.95 && shifted < 1.0);
+        assert!(smoothed < shifted && smoothed > 0.0);
+
+        let ms_same = compute_ms_ssim(&reference, &reference)
+            .unwrap()
+            .get_combined();
+        let ms_smoothed = compute_ms_ssim(&reference, &blurred)
+            .unwrap()
+            .get_combined();
+        assert!((ms_same - 1.0).abs() < 1e-12);
+        assert!(ms_smoothed < 1.0 && ms_smoothed > smoothed);
+
+        let map = compute_ssim_map(&reference, &brighter).unwrap();
+        assert_eq!(
+            (map.get_width(), map.get_height(), map.get_channels()),
+            (54, 38, 1)
+        );
+        let map_mean = map
+            .get_samples()
+            .iter()
+            .map(|ssim| *ssim as f64)
+            .sum::<f64>()
+            / (54.0 * 38.0);
+        assert!((map_mean - shifted).abs() < 1e-6);
+        let blurred_map = compute_ssim_map(&reference, &blurred).unwrap();
+        assert!(blurred_map
+            .get_samples()
+            .iter()
+            .all(|ssim| (0.0..=1.0).contains(ssim)));
+
+        let small = Images::from_samples(4, 4, 3, vec![7_u8; 48]);
+        assert!(compute_mse(&reference, &small).is_err());
+        assert_eq!(compute_ssim(&small, &small).unwrap().get_combined(), 1.0);
+    }
+
+    #[test]
+    fn histogram_chart_test() {
+        // Pure red and pure blue halves
+        let samples = (0..8)
+            .flat_map(|index| if index < 4 { [255, 0, 0] } else { [0, 0, 255] })
+            .collect();
+        let image: Images<u8> = Images::from_samples(4, 2, 3, samples);
+        let chart = HistogramChart::new(&image);
+        let options = ChartOptions::new().with_size(276, 121);
+
+        let names: Vec<&str> = chart
+            .get_series()
+            .iter()
+            .map(|series| series.get_name())
+            .collect();
+        assert_eq!(names, ["red", "green", "blue", "luminance"]);
+        assert_eq!(chart.get_series()[1].get_counts()[0], 8);
+        assert_eq!(chart.get_series()[3].get_counts()[54], 4);
+        assert_eq!(
+            HistogramChart::from_histograms(&compute_histogram(&image)).get_series(),
+            &chart.get_series()[..3]
+        );
+
+        let svg = chart.render_svg(&options);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"276\""));
+        assert_eq!(svg.matches("<polyline class=").count(), 4);
+        // Green has all 8 samples at 0, the top of the plot, and red starts at half height
+        assert!(svg.contains("<polyline class=\"green\" points=\"10.00,10.00 11.00,110.00"));
+        assert!(svg.contains("<polyline class=\"red\" points=\"10.00,60.00 "));
+        let cumulative = chart.render_svg(&options.with_cumulative(true));
+        assert!(cumulative.contains(" 265.00,10.00\" fill=\"none\" stroke=\"#dc3228\""));
+        let log_scale = chart.render_svg(&options.with_log_scale(true));
+        assert!(log_scale.contains("<polyline class=\"red\" points=\"10.00,36.75 "));
+
+        let rendered = chart.render_image(&options);
+        assert_eq!(rendered.get_width(), 276);
+        assert_eq!(rendered.get_channels(), 4);
+        assert_eq!(
+            rendered.get_pixel_samples(10, 10),
+            Some(&[40_u8, 160, 60, 255][..])
+        );
+        assert_eq!(
+            rendered.get_pixel_samples(100, 50),
+            Some(&[255_u8, 255, 255, 255][..])
+        );
+
+        let png_path = temp_path("histogram.png");
+        let svg_path = temp_path("histogram.svg");
+        chart.save(png_path.to_str().unwrap(), &options).unwrap();
+        chart.save(svg_path.to_str().unwrap(), &options).unwrap();
+        let saved = std::fs::read_to_string(&svg_path).unwrap();
+        let saved_png = std::fs::read(&png_path).unwrap();
+        std::fs::remove_file(&png_path).unwrap();
+        std::fs::remove_file(&svg_path).unwrap();
+
+        assert_eq!(saved, svg);
+        assert_eq!(Images::<u8>::from_bytes(&saved_png).unwrap(), rendered);
+    }
+
+    #[test]
+    fn image_writer_logging_test() {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+        let image: Images<u8> = Images::from_samples(1, 1, 1, vec![3]);
+        let filepath = temp_path("logging.png");
+        let filepath = filepath.to_str().unwrap();
+
+        image_writer(filepath, &image).unwrap();
+        std::fs::remove_file(filepath).unwrap();
+
+        let messages = LOGGER.messages.lock().unwrap();
+        let written: Vec<&String> = messages
+            .iter()
+            .filter(|message| message.contains(filepath))
+            .collect();
+        assert_eq!(written.len(), 2);
+        assert!(written[0].starts_with("DEBUG Writing image"));
+        assert!(written[1].starts_with("INFO Saved image"));
     }
 }